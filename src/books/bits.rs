@@ -0,0 +1,77 @@
+use crate::{interpreter::Runtime, object::NativeError, value::Value, vm::VM};
+
+use super::{number, Book};
+
+// 位經, operands are made 32-bit integers the way javascript's bitwise operators do.
+pub fn load(runtime: &mut Runtime) -> Vec<(String, Value)> {
+    let mut book = Book::new(runtime);
+
+    book.native("位與", 2, and);
+    book.native("位或", 2, or);
+    book.native("位異或", 2, xor);
+    book.native("位變", 1, not);
+    book.native("左移", 2, shift_left);
+    book.native("右移", 2, shift_right);
+    book.native("補零右移", 2, shift_right_zero);
+    book.native("位計", 1, count_ones);
+
+    book.values()
+}
+
+// ToInt32, the fraction is dropped and the rest wraps around 2^32, NaN and Infinity are 0.
+fn to_int32(num: f64) -> i32 {
+    if !num.is_finite() {
+        return 0;
+    }
+    num.trunc().rem_euclid(4294967296.0) as u32 as i32
+}
+
+// 陽 is 1 and 陰 is 0 like ToNumber, other values aren't numbers.
+fn int32(args: &[Value], index: usize) -> Result<i32, NativeError> {
+    match args[index] {
+        Value::Bool(bool) => Ok(bool as i32),
+        _ => number(args, index).map(to_int32),
+    }
+}
+
+// only the low 5 bits of the count are used, shifting by 32 is shifting by 0.
+fn shift_count(args: &[Value], index: usize) -> Result<u32, NativeError> {
+    int32(args, index).map(|count| count as u32 & 31)
+}
+
+fn and(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number((int32(args, 0)? & int32(args, 1)?) as f64))
+}
+
+fn or(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number((int32(args, 0)? | int32(args, 1)?) as f64))
+}
+
+fn xor(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number((int32(args, 0)? ^ int32(args, 1)?) as f64))
+}
+
+fn not(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(!int32(args, 0)? as f64))
+}
+
+fn shift_left(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let num = int32(args, 0)?.wrapping_shl(shift_count(args, 1)?);
+    Ok(Value::Number(num as f64))
+}
+
+// keeps the sign like >>.
+fn shift_right(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let num = int32(args, 0)? >> shift_count(args, 1)?;
+    Ok(Value::Number(num as f64))
+}
+
+// fills with zeros like >>>, the result is never negative.
+fn shift_right_zero(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let num = int32(args, 0)? as u32 >> shift_count(args, 1)?;
+    Ok(Value::Number(num as f64))
+}
+
+fn count_ones(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(int32(args, 0)?.count_ones() as f64))
+}
@@ -0,0 +1,288 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{interpreter::Runtime, object::NativeError, value::Value, vm::VM};
+
+use super::{number, Book};
+
+const STEMS: [&str; 10] = ["甲", "乙", "丙", "丁", "戊", "己", "庚", "辛", "壬", "癸"];
+const BRANCHES: [&str; 12] = [
+    "子", "丑", "寅", "卯", "辰", "巳", "午", "未", "申", "酉", "戌", "亥",
+];
+
+// 曆法, the time is read from the Clock of the runtime, in UTC.
+pub fn load(runtime: &mut Runtime) -> Vec<(String, Value)> {
+    let mut book = Book::new(runtime);
+
+    book.native("取年", 0, year);
+    book.native("取月", 0, month);
+    book.native("取日", 0, day);
+    book.native("取時", 0, hour);
+    book.native("取分", 0, minute);
+    book.native("取秒", 0, second);
+    book.native("取星期", 0, weekday);
+    book.native("取時刻", 0, timestamp);
+    book.native("計時", 0, elapsed);
+    book.native("干支", 1, stem_branch);
+    book.native("時辰", 1, double_hour);
+
+    book.values()
+}
+
+// where the time comes from, hosts and tests can pin it with Runtime::set_clock.
+pub trait Clock {
+    // since 1970-01-01 00:00:00 UTC.
+    fn now(&self) -> Duration;
+    // since the last `start`, it never goes backwards.
+    fn elapsed(&self) -> Duration;
+    // interpret calls it right before the script runs.
+    fn start(&mut self) {}
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+    fn start(&mut self) {
+        self.start = Instant::now();
+    }
+}
+
+struct DateTime {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    // 0 is Sunday like Date.getDay.
+    weekday: i64,
+}
+
+impl DateTime {
+    // days to a Gregorian date, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    fn from_secs(secs: i64) -> Self {
+        let days = secs.div_euclid(86400);
+        let secs = secs.rem_euclid(86400);
+
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Self {
+            year,
+            month,
+            day,
+            hour: secs / 3600,
+            minute: secs % 3600 / 60,
+            second: secs % 60,
+            // 1970-01-01 is a Thursday.
+            weekday: (days + 4).rem_euclid(7),
+        }
+    }
+}
+
+fn now(vm: &mut VM) -> DateTime {
+    DateTime::from_secs(vm.runtime_mut().clock().now().as_secs() as i64)
+}
+
+fn year(vm: &mut VM, _args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(now(vm).year as f64))
+}
+
+fn month(vm: &mut VM, _args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(now(vm).month as f64))
+}
+
+fn day(vm: &mut VM, _args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(now(vm).day as f64))
+}
+
+fn hour(vm: &mut VM, _args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(now(vm).hour as f64))
+}
+
+fn minute(vm: &mut VM, _args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(now(vm).minute as f64))
+}
+
+fn second(vm: &mut VM, _args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(now(vm).second as f64))
+}
+
+fn weekday(vm: &mut VM, _args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(now(vm).weekday as f64))
+}
+
+// milliseconds since 1970 like Date.now.
+fn timestamp(vm: &mut VM, _args: &[Value]) -> Result<Value, NativeError> {
+    let millis = vm.runtime_mut().clock().now().as_millis();
+    Ok(Value::Number(millis as f64))
+}
+
+// milliseconds since the script started, only the difference of two calls makes sense.
+fn elapsed(vm: &mut VM, _args: &[Value]) -> Result<Value, NativeError> {
+    let elapsed = vm.runtime_mut().clock().elapsed();
+    Ok(Value::Number(elapsed.as_secs_f64() * 1000.0))
+}
+
+// 施「干支」於二千二十四 is 甲辰, the year is taken as a whole
+// without waiting for the lunar new year, 0 is 1 BC.
+fn stem_branch(vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let year = number(args, 0)?;
+    if !year.is_finite() {
+        return Err(NativeError::from("expect a year."));
+    }
+    // 4 AD is 甲子.
+    let cycle = (year.trunc() as i64 - 4).rem_euclid(60) as usize;
+    let name = format!("{}{}", STEMS[cycle % 10], BRANCHES[cycle % 12]);
+    Ok(Value::String(vm.runtime_mut().interner_mut().intern(&name)))
+}
+
+// a 時辰 is two hours, 子時 starts at 23 of the day before.
+fn double_hour(vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let hour = number(args, 0)?.floor();
+    if !(0.0..24.0).contains(&hour) {
+        return Err(NativeError::from("expect an hour from 0 to 23."));
+    }
+    let branch = (hour as usize).div_ceil(2) % 12;
+    let name = format!("{}時", BRANCHES[branch]);
+    Ok(Value::String(vm.runtime_mut().interner_mut().intern(&name)))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::Clock;
+    use crate::interpreter::{testing, InterpretStatus, Runtime};
+
+    struct FixedClock;
+
+    impl Clock for FixedClock {
+        // 2024-02-10 13:45:30 UTC, a Saturday.
+        fn now(&self) -> Duration {
+            Duration::from_secs(1707572730)
+        }
+        fn elapsed(&self) -> Duration {
+            Duration::from_millis(1500)
+        }
+    }
+
+    // counts from when it's made until interpret starts it.
+    struct StartedClock {
+        started: bool,
+    }
+
+    impl Clock for StartedClock {
+        fn now(&self) -> Duration {
+            Duration::ZERO
+        }
+        fn elapsed(&self) -> Duration {
+            Duration::from_secs(if self.started { 0 } else { 60 })
+        }
+        fn start(&mut self) {
+            self.started = true;
+        }
+    }
+
+    fn run(buf: &str) -> (InterpretStatus, Vec<String>) {
+        testing::run(buf, |runtime: &mut Runtime| {
+            runtime.set_clock(Box::new(FixedClock))
+        })
+    }
+
+    #[test]
+    fn test_calendar_now() {
+        let (status, recorded) = run("吾嘗觀「「曆法」」之書
+            方悟「取年」「取月」「取日」「取時」「取分」「取秒」「取星期」「取時刻」「計時」之義
+            施「取年」施「記」於其
+            施「取月」施「記」於其
+            施「取日」施「記」於其
+            施「取時」施「記」於其
+            施「取分」施「記」於其
+            施「取秒」施「記」於其
+            施「取星期」施「記」於其
+            施「取時刻」施「記」於其
+            施「計時」施「記」於其");
+
+        assert_eq!(status, InterpretStatus::Ok);
+        assert_eq!(
+            recorded,
+            vec![
+                "2024",
+                "2",
+                "10",
+                "13",
+                "45",
+                "30",
+                "6",
+                "1707572730000",
+                "1500"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_calendar_names() {
+        let (status, recorded) = run(
+            "吾嘗觀「「曆法」」之書方悟「干支」「時辰」「取年」「取時」之義
+            施「取年」施「干支」於其施「記」於其
+            施「取時」施「時辰」於其施「記」於其
+            施「干支」於一千九百八十四施「記」於其
+            施「干支」於零施「記」於其
+            施「時辰」於零施「記」於其
+            施「時辰」於二十三施「記」於其
+            施「時辰」於一施「記」於其
+            施「時辰」於二十四",
+        );
+
+        assert_eq!(status, InterpretStatus::RuntimeError);
+        assert_eq!(
+            recorded,
+            vec!["甲辰", "未時", "甲子", "庚申", "子時", "子時", "丑時"]
+        );
+    }
+
+    #[test]
+    fn test_calendar_elapsed_from_start() {
+        let (status, recorded) = testing::run(
+            "吾嘗觀「「曆法」」之書方悟「計時」之義施「計時」施「記」於其",
+            |runtime: &mut Runtime| runtime.set_clock(Box::new(StartedClock { started: false })),
+        );
+
+        assert_eq!(status, InterpretStatus::Ok);
+        assert_eq!(recorded, vec!["0"]);
+    }
+
+    #[test]
+    fn test_date_time() {
+        let date = super::DateTime::from_secs(951782400);
+        assert_eq!((date.year, date.month, date.day), (2000, 2, 29));
+        let date = super::DateTime::from_secs(-1);
+        assert_eq!((date.year, date.month, date.day), (1969, 12, 31));
+        assert_eq!((date.hour, date.minute, date.second), (23, 59, 59));
+    }
+}
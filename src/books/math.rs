@@ -0,0 +1,126 @@
+use std::f64::consts;
+
+use crate::{interpreter::Runtime, object::NativeError, value::Value, vm::VM};
+
+use super::{number, Book};
+
+// 算經, every 術 gives what javascript's Math gives, see the table in the README.
+pub fn load(runtime: &mut Runtime) -> Vec<(String, Value)> {
+    let mut book = Book::new(runtime);
+
+    book.constant("圓周率", Value::Number(consts::PI));
+    book.constant("倍圓周率", Value::Number(consts::TAU));
+    book.constant("半圓周率", Value::Number(consts::FRAC_PI_2));
+    book.constant("四分圓周率", Value::Number(consts::FRAC_PI_4));
+    book.constant("自然常數", Value::Number(consts::E));
+    book.constant("歐拉常數", Value::Number(0.5772156649015329));
+    book.constant("黃金分割數", Value::Number(1.618033988749895));
+    book.constant("二之平方根", Value::Number(consts::SQRT_2));
+    book.constant("二之對數", Value::Number(consts::LN_2));
+    book.constant("十之對數", Value::Number(consts::LN_10));
+
+    book.native("正弦", 1, sin);
+    book.native("餘弦", 1, cos);
+    book.native("正切", 1, tan);
+    book.native("反正弦", 1, asin);
+    book.native("反餘弦", 1, acos);
+    book.native("反正切", 1, atan);
+    book.native("勾股求角", 2, atan2);
+    book.native("勾股求弦", 2, hypot);
+    book.native("平方根", 1, sqrt);
+    book.native("立方根", 1, cbrt);
+    book.native("冪", 2, pow);
+    book.native("指數", 1, exp);
+    book.native("對數", 1, ln);
+    book.native("取底", 1, floor);
+    book.native("取頂", 1, ceil);
+    book.native("取整", 1, round);
+    book.native("取絕對", 1, abs);
+    book.native("取符", 1, sign);
+
+    book.values()
+}
+
+fn sin(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.sin()))
+}
+
+fn cos(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.cos()))
+}
+
+fn tan(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.tan()))
+}
+
+fn asin(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.asin()))
+}
+
+fn acos(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.acos()))
+}
+
+fn atan(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.atan()))
+}
+
+// 施「勾股求角」於股於勾, the angle of the point (勾, 股).
+fn atan2(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.atan2(number(args, 1)?)))
+}
+
+fn hypot(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.hypot(number(args, 1)?)))
+}
+
+fn sqrt(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.sqrt()))
+}
+
+fn cbrt(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.cbrt()))
+}
+
+fn pow(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.powf(number(args, 1)?)))
+}
+
+fn exp(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.exp()))
+}
+
+fn ln(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.ln()))
+}
+
+fn floor(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.floor()))
+}
+
+fn ceil(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.ceil()))
+}
+
+// halves go up like Math.round, -2.5 is -2 instead of -3.
+fn round(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let num = number(args, 0)?;
+    let rounded = num.round();
+    match num - rounded == 0.5 {
+        true => Ok(Value::Number(rounded + 1.0)),
+        false => Ok(Value::Number(rounded)),
+    }
+}
+
+fn abs(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(number(args, 0)?.abs()))
+}
+
+// zero keeps its sign like Math.sign, where f64::signum would give 1.
+fn sign(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let num = number(args, 0)?;
+    match num == 0.0 || num.is_nan() {
+        true => Ok(Value::Number(num)),
+        false => Ok(Value::Number(num.signum())),
+    }
+}
@@ -0,0 +1,71 @@
+mod bits;
+mod calendar;
+mod math;
+mod random;
+
+use crate::{
+    interpreter::Runtime,
+    object::{ListId, Native, NativeError, NativeFn},
+    value::{Kind, Value},
+};
+
+pub use calendar::{Clock, SystemClock};
+pub use random::Rng;
+
+// builds what a built-in book exports, it runs the first time the book is imported.
+pub type BookLoader = fn(&mut Runtime) -> Vec<(String, Value)>;
+
+// the books shipped with the interpreter, a 名.wy file next to the script still wins.
+pub fn register(runtime: &mut Runtime) {
+    runtime.add_book("算經", math::load);
+    runtime.add_book("位經", bits::load);
+    runtime.add_book("渾沌經", random::load);
+    runtime.add_book("易經", random::load);
+    runtime.add_book("曆法", calendar::load);
+}
+
+pub struct Book<'a> {
+    runtime: &'a mut Runtime,
+    values: Vec<(String, Value)>,
+}
+
+impl<'a> Book<'a> {
+    pub fn new(runtime: &'a mut Runtime) -> Self {
+        Self {
+            runtime,
+            values: vec![],
+        }
+    }
+    pub fn constant(&mut self, name: &str, value: Value) {
+        self.values.push((name.to_owned(), value));
+    }
+    pub fn native(&mut self, name: &str, arity: usize, function: NativeFn) {
+        let id = self.runtime.add_native(Native::new(name, arity, function));
+        self.values.push((name.to_owned(), Value::Native(id)));
+    }
+    pub fn values(self) -> Vec<(String, Value)> {
+        self.values
+    }
+}
+
+pub fn number(args: &[Value], index: usize) -> Result<f64, NativeError> {
+    match args[index] {
+        Value::Number(num) => Ok(num),
+        value => Err(NativeError::Message(format!(
+            "expect a {} but got a {}.",
+            Kind::Number.name(),
+            Kind::name_of(&value)
+        ))),
+    }
+}
+
+pub fn list(args: &[Value], index: usize) -> Result<ListId, NativeError> {
+    match args[index] {
+        Value::List(id) => Ok(id),
+        value => Err(NativeError::Message(format!(
+            "expect a {} but got a {}.",
+            Kind::List.name(),
+            Kind::name_of(&value)
+        ))),
+    }
+}
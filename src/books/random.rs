@@ -0,0 +1,110 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{interpreter::Runtime, object::NativeError, value::Value, vm::VM};
+
+use super::{list, number, Book};
+
+const MAX_SAFE_RANGE: f64 = 9007199254740992.0;
+
+// 渾沌經, every number comes from the Rng of the runtime, see Runtime::set_seed.
+pub fn load(runtime: &mut Runtime) -> Vec<(String, Value)> {
+    let mut book = Book::new(runtime);
+
+    book.native("隨機數", 0, random);
+    book.native("隨機整數", 2, random_integer);
+    book.native("隨機取", 1, choice);
+    book.native("洗牌", 1, shuffle);
+    book.native("播種", 1, seed);
+
+    book.values()
+}
+
+// splitmix64, small and fast, the same seed always gives the same numbers.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    pub fn from_time() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::new(seed)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+    // in [0, 1), with the 53 bits a f64 can hold.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+    // in [0, bound), numbers past the last whole multiple of `bound` are thrown away so none is favored.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let num = self.next_u64();
+            if num < zone {
+                return num % bound;
+            }
+        }
+    }
+}
+
+fn random(vm: &mut VM, _args: &[Value]) -> Result<Value, NativeError> {
+    Ok(Value::Number(vm.runtime_mut().rng_mut().next_f64()))
+}
+
+// both ends are included, 施「隨機整數」於一於六 is a dice.
+fn random_integer(vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let low = number(args, 0)?.ceil();
+    let high = number(args, 1)?.floor();
+    if !low.is_finite() || !high.is_finite() || low > high {
+        return Err(NativeError::from(
+            "expect a range with at least one integer.",
+        ));
+    }
+    // past 2^53 not every integer is a f64, and the count would overflow a u64.
+    if high - low >= MAX_SAFE_RANGE {
+        return Err(NativeError::from(
+            "expect a range of at most 2^53 integers.",
+        ));
+    }
+    let offset = vm.runtime_mut().rng_mut().below((high - low) as u64 + 1);
+    Ok(Value::Number(low + offset as f64))
+}
+
+fn choice(vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let id = list(args, 0)?;
+    let len = vm.runtime_mut().get_list(&id).len();
+    if len == 0 {
+        return Err(NativeError::from("cannot choose from an empty 列."));
+    }
+    let index = vm.runtime_mut().rng_mut().below(len as u64) as usize;
+    Ok(vm.runtime_mut().get_list(&id)[index])
+}
+
+// shuffles the 列 in place and gives it back.
+fn shuffle(vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let id = list(args, 0)?;
+    let runtime = vm.runtime_mut();
+    for i in (1..runtime.get_list(&id).len()).rev() {
+        let j = runtime.rng_mut().below(i as u64 + 1) as usize;
+        runtime.get_list_mut(&id).swap(i, j);
+    }
+    Ok(args[0])
+}
+
+// negative seeds are 0, fractions are dropped.
+fn seed(vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+    let seed = number(args, 0)?;
+    vm.runtime_mut().set_seed(seed as u64);
+    Ok(Value::Nil)
+}
@@ -1,24 +1,29 @@
-use std::mem;
+use std::{collections::HashMap, fs, mem};
 
 use crate::{
     chunk::Chunk,
     convert::hanzi2num::hanzi2num,
     interpreter::Runtime,
-    object::{ClosureId, Function},
+    macros::expand,
+    module::{find_module, Module, ModuleId},
+    object::{ClosureId, FunId, Function, UpValue},
     opcode,
     statements::{
-        assign_statement, binary_statement, boolean_algebra_statement, break_statement,
-        call_statement, expression_statement, for_statement, for_while_statement, fun_statement,
-        if_statement, name_is_statement, normal_declaration, print_statement, return_statement,
-        short_declaration, unary_statement,
+        assign_statement, binary_if_expression, binary_statement, boolean_algebra_statement,
+        break_statement, call_statement, clear_statement, continue_statement, expression_statement,
+        for_each_statement, for_statement, for_while_statement, fun_statement, if_statement,
+        import_statement, name_is_statement, normal_declaration, prefix_call_statement,
+        print_statement, push_statement, return_statement, short_declaration, take_call_statement,
+        throw_statement, try_statement, unary_statement,
     },
     tokenize::{position::WithSpan, scanner::Scanner, token::Token},
-    value::Value,
+    value::{Kind, Value},
 };
 
 pub struct Local {
     name: String,
     depth: Depth,
+    kind: Option<Kind>,
 }
 
 impl Default for Local {
@@ -26,6 +31,7 @@ impl Default for Local {
         Self {
             name: String::default(),
             depth: 0,
+            kind: None,
         }
     }
 }
@@ -45,6 +51,12 @@ pub struct Compiler {
     function: Function,
     fun_kind: FunctionType,
     enclosing: Option<Box<Compiler>>,
+    loop_depth: usize,
+    up_values: Vec<UpValue>,
+    // whether the value produced by the latest statement is still on top of stack,
+    // under the operands of current statement.
+    prev_on_stack: bool,
+    operands: usize,
 }
 
 impl Compiler {
@@ -55,6 +67,10 @@ impl Compiler {
             function: Function::new(),
             fun_kind,
             enclosing: None,
+            loop_depth: 0,
+            up_values: vec![],
+            prev_on_stack: false,
+            operands: 0,
         })
     }
     pub fn begin_scope(&mut self) {
@@ -67,6 +83,7 @@ impl Compiler {
         self.locals.push(Local {
             name,
             depth: self.scope_depth,
+            kind: None,
         });
     }
     pub fn set_enclosing(&mut self, enclosing: Box<Compiler>) {
@@ -81,12 +98,51 @@ impl Compiler {
 
         None
     }
+    // Some(None) for a variable declared without a type.
+    pub fn resolve_kind(&self, name: &str) -> Option<Option<Kind>> {
+        match self.locals.iter().rev().find(|local| local.name == name) {
+            Some(local) => Some(local.kind),
+            None => self.enclosing.as_ref()?.resolve_kind(name),
+        }
+    }
+    // look for the variable in the enclosing functions, the script itself has nothing to capture.
+    pub fn resolve_up_value(&mut self, name: String) -> Option<u32> {
+        let enclosing = self.enclosing.as_mut()?;
+        if enclosing.fun_kind == FunctionType::Empty {
+            return None;
+        }
+
+        if let Some(index) = enclosing.resolve_local(name.clone()) {
+            return Some(self.add_up_value(index, true));
+        }
+
+        if let Some(index) = enclosing.resolve_up_value(name) {
+            return Some(self.add_up_value(index, false));
+        }
+
+        None
+    }
+    fn add_up_value(&mut self, index: u32, is_local: bool) -> u32 {
+        let up_value = UpValue { index, is_local };
+        if let Some(i) = self.up_values.iter().position(|x| *x == up_value) {
+            return i as u32;
+        }
+
+        self.up_values.push(up_value);
+        (self.up_values.len() - 1) as u32
+    }
+    pub fn up_values(&self) -> &Vec<UpValue> {
+        &self.up_values
+    }
     pub fn function_mut(&mut self) -> &mut Function {
         &mut self.function
     }
     pub fn fun_kind(&self) -> FunctionType {
         self.fun_kind
     }
+    pub fn loop_depth(&self) -> usize {
+        self.loop_depth
+    }
 }
 
 pub struct Parser<'a> {
@@ -98,14 +154,19 @@ pub struct Parser<'a> {
     panic_mode: bool,
     runtime: &'a mut Runtime,
     current_compiler: Box<Compiler>,
+    module: ModuleId,
+    // inside a 今有 declaration, the globals it defines are exported.
+    public_declaration: bool,
+    global_kinds: HashMap<String, Kind>,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(buf: &'a str, runtime: &'a mut Runtime) -> Self {
+    pub fn new(buf: &'a str, runtime: &'a mut Runtime, module: ModuleId) -> Self {
         let scanner = Scanner::new(buf);
 
         let mut compiler = Compiler::init(FunctionType::Script);
         compiler.enclosing = Some(Compiler::init(FunctionType::Empty));
+        compiler.function_mut().set_module(module);
 
         Self {
             scanner,
@@ -116,8 +177,14 @@ impl<'a> Parser<'a> {
             panic_mode: false,
             runtime,
             current_compiler: compiler,
+            module,
+            public_declaration: false,
+            global_kinds: HashMap::new(),
         }
     }
+    pub fn set_line_map(&mut self, line_map: Vec<usize>) {
+        self.scanner.set_line_map(line_map);
+    }
     pub fn current_code_len(&self) -> usize {
         self.current_compiler.function.chunk().code().len()
     }
@@ -148,7 +215,8 @@ impl<'a> Parser<'a> {
         let function = self.end_compiler();
 
         if let Some(function) = function {
-            return Some(self.runtime.add_closure(function));
+            let fun_id = self.runtime.add_function(function);
+            return Some(self.runtime.add_closure(fun_id, vec![]));
         }
 
         None
@@ -159,7 +227,9 @@ impl<'a> Parser<'a> {
         self.current_compiler.set_enclosing(old_compiler);
 
         let fn_name = self.pick_str(self.previous()).to_string();
+        let module = self.module;
         self.current_compiler.function_mut().set_name(fn_name);
+        self.current_compiler.function_mut().set_module(module);
     }
     pub fn end_compiler(&mut self) -> Option<Function> {
         self.emit_return();
@@ -178,19 +248,156 @@ impl<'a> Parser<'a> {
             fun
         }
     }
-    pub fn add_closure(&mut self, fun: Function) -> ClosureId {
-        self.runtime.add_closure(fun)
+    // only globals declared with 今有 can be seen by the scripts importing this one.
+    pub fn export_global(&mut self, name: String) {
+        if self.public_declaration {
+            self.runtime.get_module_mut(self.module).add_export(name);
+        }
+    }
+    // remember the type of the variable just named, a name without type forgets it.
+    pub fn declare_kind(&mut self, kind: Option<Kind>) {
+        let name = self.get_prev_token_string();
+        if self.get_scope() > 0 {
+            if let Some(local) = self.current_compiler.locals.last_mut() {
+                local.kind = kind;
+            }
+            return;
+        }
+
+        match kind {
+            Some(kind) => self.global_kinds.insert(name, kind),
+            None => self.global_kinds.remove(&name),
+        };
+    }
+    pub fn variable_kind(&self, name: &str) -> Option<Kind> {
+        match self.current_compiler.resolve_kind(name) {
+            Some(kind) => kind,
+            None => self.global_kinds.get(name).copied(),
+        }
+    }
+    // compile an expression going into a place declared as `kind`,
+    // literals and typed variables are checked here, the rest are checked at runtime.
+    pub fn typed_expression(&mut self, kind: Option<Kind>) {
+        let start = self.current().get_start();
+        let found = match self.current().get_value() {
+            Token::Number => Some(Kind::Number),
+            Token::String => Some(Kind::String),
+            Token::True | Token::False => Some(Kind::Bool),
+            Token::Identifier => {
+                let name = self.pick_str(self.current()).to_string();
+                self.variable_kind(&name)
+            }
+            _ => None,
+        };
+
+        self.expression();
+
+        let kind = match kind {
+            Some(kind) if kind != Kind::Any && self.runtime.strict_types() => kind,
+            _ => return,
+        };
+
+        // 「「文」」之長 is a number, only a single token tells its type.
+        match found {
+            Some(found) if self.previous().get_start() == start => {
+                if found != kind && found != Kind::Any {
+                    self.error(&format!(
+                        "expect a {} but got a {}.",
+                        kind.name(),
+                        found.name()
+                    ));
+                }
+            }
+            _ => {
+                self.emit_u8(opcode::CHECK_TYPE);
+                self.emit_u8(kind as u8);
+            }
+        }
+    }
+    pub fn module_exports(&self, module: ModuleId) -> &Vec<String> {
+        self.runtime.get_module(module).exports()
+    }
+    // compile the book once, later imports share the cached module.
+    pub fn load_module(&mut self, name: &str) -> Option<ModuleId> {
+        let dir = self.runtime.get_module(self.module).dir();
+        let path = match find_module(name, dir, self.runtime.search_paths()) {
+            Some(path) => path,
+            None => {
+                if let Some(module) = self.runtime.load_book(name) {
+                    return Some(module);
+                }
+                self.error(format!("cannot find book {}.", name).as_str());
+                return None;
+            }
+        };
+
+        if let Some(module) = self.runtime.module_id(&path) {
+            let module_ref = self.runtime.get_module(module);
+            // its errors were reported when it was first imported.
+            if module_ref.is_failed() {
+                return None;
+            }
+            if module_ref.is_loading() {
+                self.error(format!("circular import of book {}.", name).as_str());
+                return None;
+            }
+            return Some(module);
+        }
+
+        let module = self.runtime.add_module(Module::new(Some(path.clone())));
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) => {
+                self.runtime.get_module_mut(module).set_failed();
+                self.error(format!("cannot read book {}: {}.", name, e).as_str());
+                return None;
+            }
+        };
+
+        let source = match expand(&source) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("[line {}] compiler error: {}", e.line, e.msg);
+                self.runtime.get_module_mut(module).set_failed();
+                self.error(format!("cannot compile book {}.", name).as_str());
+                return None;
+            }
+        };
+
+        let mut parser = Parser::new(source.text(), self.runtime, module);
+        parser.set_line_map(source.line_map().clone());
+        let closure = parser.compile();
+        match closure {
+            Some(closure) => {
+                self.runtime.get_module_mut(module).set_closure(closure);
+                Some(module)
+            }
+            None => {
+                self.runtime.get_module_mut(module).set_failed();
+                self.error(format!("cannot compile book {}.", name).as_str());
+                None
+            }
+        }
+    }
+    pub fn add_function(&mut self, fun: Function) -> FunId {
+        self.runtime.add_function(fun)
     }
     pub fn declaration(&mut self) {
+        self.current_compiler.operands = 0;
+
         if self.is_match(Token::Decl) {
             self.normal_declaration();
+        } else if self.is_match(Token::PublicDecl) {
+            self.public_declaration = true;
+            self.normal_declaration();
+            self.public_declaration = false;
         } else if self.is_match(Token::DeclShort) {
             self.short_declaration()
         } else {
             self.statement();
         }
 
-        if self.has_error {
+        if self.panic_mode {
             self.synchronize();
         }
     }
@@ -213,13 +420,25 @@ impl<'a> Parser<'a> {
         let current = self.current.as_ref().unwrap().get_value().clone();
 
         match current {
-            Token::Plus | Token::Minus | Token::Star => binary_statement(self, &current),
+            Token::Plus | Token::Minus | Token::Star | Token::Slash => {
+                binary_statement(self, &current)
+            }
             Token::Invert => unary_statement(self, &current),
             Token::Print => print_statement(self),
 
             Token::AssignFrom => assign_statement(self),
             Token::NameIs => name_is_statement(self),
-            Token::If => if_statement(self),
+            Token::If | Token::IfTrue | Token::IfFalse => if_statement(self),
+            // 大於十 compares the value on top of stack, it's already there.
+            Token::EqualEqual
+            | Token::BangEqual
+            | Token::Greater
+            | Token::Less
+            | Token::BangGreater
+            | Token::BangLess => {
+                self.consume_value();
+                binary_if_expression(self)
+            }
             Token::Fu => {
                 self.advance();
                 self.expression();
@@ -233,12 +452,81 @@ impl<'a> Parser<'a> {
                 for_while_statement(self);
             }
             Token::For => for_statement(self),
+            Token::ForEach => for_each_statement(self),
             Token::Break => break_statement(self),
+            Token::Continue => continue_statement(self),
             Token::Fun => self.fun_statement(),
+            Token::PublicFun => {
+                self.public_declaration = true;
+                self.fun_statement();
+                self.public_declaration = false;
+            }
+            Token::Import => import_statement(self),
             Token::Call => self.call_statement(),
-            Token::Return => self.return_statement(),
+            Token::PrepositionRight => prefix_call_statement(self),
+            Token::Take => take_call_statement(self),
+            Token::Return | Token::ReturnPrev | Token::ReturnNil => self.return_statement(),
+            Token::Push => push_statement(self),
+            Token::Try => try_statement(self),
+            Token::Throw => throw_statement(self),
+            Token::Clear => clear_statement(self),
             _ => expression_statement(self),
         }
+
+        // the rest of statements leave a value on stack, 其 refers to it.
+        match current {
+            Token::Print
+            | Token::AssignFrom
+            | Token::NameIs
+            | Token::If
+            | Token::IfTrue
+            | Token::IfFalse
+            | Token::Loop
+            | Token::For
+            | Token::ForEach
+            | Token::Break
+            | Token::Continue
+            | Token::Fun
+            | Token::PublicFun
+            | Token::Import
+            | Token::Return
+            | Token::ReturnPrev
+            | Token::ReturnNil
+            | Token::Push
+            | Token::Try
+            | Token::Throw
+            | Token::Clear => {}
+            _ => self.produce_value(),
+        }
+    }
+    pub fn produce_value(&mut self) {
+        self.emit_u8(opcode::SET_PREV);
+        self.current_compiler.prev_on_stack = true;
+    }
+    // the value has been named, printed or used by 若其然者, 其 gets a copy of it since then.
+    pub fn consume_value(&mut self) {
+        self.current_compiler.prev_on_stack = false;
+    }
+    pub fn push_operands(&mut self, count: usize) {
+        self.current_compiler.operands += count;
+    }
+    pub fn pop_operands(&mut self, count: usize) {
+        self.current_compiler.operands = self.current_compiler.operands.saturating_sub(count);
+    }
+    // 其 moves the latest value to top of stack if nobody took it,
+    // so 加「甲」以一。昔之「甲」者今其是矣 doesn't leave the sum behind.
+    pub fn prev_value(&mut self) {
+        if !self.current_compiler.prev_on_stack {
+            self.emit_u8(opcode::GET_PREV);
+            return;
+        }
+
+        self.current_compiler.prev_on_stack = false;
+        let depth = self.current_compiler.operands as u32;
+        if depth > 0 {
+            self.emit_u8(opcode::TAKE_PREV);
+            self.emit_u32(depth);
+        }
     }
 
     fn synchronize(&mut self) {
@@ -248,20 +536,45 @@ impl<'a> Parser<'a> {
 
             match current {
                 Token::Decl
+                | Token::PublicDecl
                 | Token::DeclShort
                 | Token::Fun
+                | Token::PublicFun
+                | Token::Import
                 | Token::Call
+                | Token::Take
                 | Token::Print
+                | Token::Clear
                 | Token::If
+                | Token::IfTrue
+                | Token::IfFalse
                 | Token::Fu
                 | Token::Loop
                 | Token::For
+                | Token::ForEach
                 | Token::Break
+                | Token::Return
+                | Token::ReturnPrev
+                | Token::ReturnNil
+                | Token::Continue
+                | Token::Push
+                | Token::Try
+                | Token::Throw
                 | Token::AssignFrom
                 | Token::Plus
                 | Token::Minus
                 | Token::Star
+                | Token::Slash
                 | Token::Invert
+                // the end of a block, let the block statement close itself.
+                | Token::YunYun
+                | Token::Else
+                | Token::ElseIf
+                | Token::Catch
+                | Token::CatchError
+                | Token::CatchAll
+                | Token::TryEnd
+                | Token::FunctionEnd1
                 | Token::Eof => return,
                 _ => {
                     self.advance();
@@ -327,7 +640,11 @@ impl<'a> Parser<'a> {
     pub fn advance(&mut self) {
         self.previous = self.current.take();
 
-        let token = self.scanner.scan_token();
+        // comments are only kept for tooling, the compiler never sees them.
+        let mut token = self.scanner.scan_token();
+        while *token.get_value() == Token::Comment {
+            token = self.scanner.scan_token();
+        }
 
         let msg = match token.get_value() {
             Token::Error(msg) => Some(msg.to_owned()),
@@ -365,7 +682,12 @@ impl<'a> Parser<'a> {
 
     pub fn is_match_literal(&self) -> bool {
         match self.current.as_ref().unwrap().get_value() {
-            Token::True | Token::False | Token::String | Token::Number | Token::Identifier => true,
+            Token::True
+            | Token::False
+            | Token::String
+            | Token::Number
+            | Token::Identifier
+            | Token::Prev => true,
             _ => false,
         }
     }
@@ -420,6 +742,9 @@ impl<'a> Parser<'a> {
         let s = &self.buf[start..end];
         Value::String(self.runtime.interner_mut().intern(s))
     }
+    pub fn string_value(&mut self, s: &str) -> Value {
+        Value::String(self.runtime.interner_mut().intern(s))
+    }
     fn number(&mut self) {
         let s = self.pick_str(&self.previous());
         let num_str = hanzi2num(s);
@@ -443,7 +768,10 @@ impl<'a> Parser<'a> {
 
         let (x, y) = match arg {
             Some(arg) => (opcode::GET_LOCAL, arg),
-            None => (opcode::GET_GLOBAL, self.identifier_constant().unwrap()),
+            None => match self.resolve_up_value(self.get_prev_token_string()) {
+                Some(arg) => (opcode::GET_UPVALUE, arg),
+                None => (opcode::GET_GLOBAL, self.identifier_constant().unwrap()),
+            },
         };
 
         self.emit_u8(x);
@@ -461,12 +789,48 @@ impl<'a> Parser<'a> {
                 self.emit_constant(value)
             }
             Token::Identifier => self.variable(),
-            Token::Prev => {} // do nothing
+            Token::Prev => self.prev_value(),
             _ => {
-                println!("{:?}", self.previous().get_value());
                 self.error("Expect expression");
             }
         }
+
+        while self.check_in_vec(&[Token::Of, Token::Length, Token::Rest]) {
+            self.subscript();
+        }
+
+        self.push_operands(1);
+    }
+    fn subscript(&mut self) {
+        self.advance();
+        match *self.previous().get_value() {
+            Token::Length => self.emit_u8(opcode::LENGTH),
+            Token::Rest => self.emit_u8(opcode::REST),
+            Token::Of => {
+                if self.index_key() {
+                    self.emit_u8(opcode::INDEX);
+                }
+            }
+            _ => self.error("unknown subscript."),
+        }
+    }
+    // 之一 for a list, 之「「鍵」」 for an object, or 之「乙」 for both.
+    pub fn index_key(&mut self) -> bool {
+        self.advance();
+        match *self.previous().get_value() {
+            Token::Number => self.number(),
+            Token::Identifier => self.variable(),
+            Token::String => {
+                let value = self.str_to_value();
+                self.emit_constant(value)
+            }
+            _ => {
+                self.error("expect an index after '之'.");
+                return false;
+            }
+        }
+
+        true
     }
     pub fn begin_scope(&mut self) {
         self.current_compiler.begin_scope()
@@ -477,6 +841,9 @@ impl<'a> Parser<'a> {
     pub fn resolve_local(&mut self, name: String) -> Option<u32> {
         self.current_compiler.resolve_local(name)
     }
+    pub fn resolve_up_value(&mut self, name: String) -> Option<u32> {
+        self.current_compiler.resolve_up_value(name)
+    }
     pub fn end_scope(&mut self) {
         self.current_compiler.scope_depth -= 1;
         while !self.current_compiler.locals.is_empty()
@@ -490,6 +857,12 @@ impl<'a> Parser<'a> {
     pub fn add_local(&mut self, name: String) {
         self.current_compiler.add_local(name);
     }
+    pub fn begin_loop(&mut self) {
+        self.current_compiler.loop_depth += 1;
+    }
+    pub fn end_loop(&mut self) {
+        self.current_compiler.loop_depth -= 1;
+    }
     pub fn emit_jump(&mut self, opcode: u8) -> usize {
         self.emit_u8(opcode);
         self.emit_u32(0);
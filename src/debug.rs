@@ -54,6 +54,12 @@ impl<'a> Debugger<'a> {
             opcode::MULTIPLY => {
                 self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_MULTIPLY")
             }
+            opcode::DIVIDE => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_DIVIDE")
+            }
+            opcode::REMAINDER => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_REMAINDER")
+            }
             opcode::TRUE => {
                 self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_TRUE")
             }
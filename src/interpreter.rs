@@ -1,25 +1,51 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use crate::{
+    books::{self, BookLoader, Clock, Rng, SystemClock},
     chunk::Chunk,
     compiler::Parser,
     interner::Interner,
-    object::{Closure, ClosureId, FunId, Function},
+    macros::expand,
+    module::{Module, ModuleId},
+    object::{
+        Captured, Closure, ClosureId, FunId, Function, ListId, Native, NativeFn, NativeId, Object,
+        ObjectId, UpValueId,
+    },
+    value::Value,
     vm::{VMMode, VM},
 };
 
+#[derive(Debug, PartialEq)]
 pub enum InterpretStatus {
     CompilerError,
     RuntimeError,
     Ok,
 }
 
-pub fn interpret(buf: &str, mode: VMMode) -> InterpretStatus {
-    let mut runtime = Runtime::new();
-    let mut compiler = Parser::new(buf, &mut runtime);
+// `path` is where the script lives, books it imports are looked up next to it.
+pub fn interpret(
+    runtime: &mut Runtime,
+    buf: &str,
+    path: Option<PathBuf>,
+    mode: VMMode,
+) -> InterpretStatus {
+    let path = path.and_then(|path| path.canonicalize().ok());
+    let module = runtime.add_module(Module::new(path));
+    let buf = match expand(buf) {
+        Ok(buf) => buf,
+        Err(e) => {
+            eprintln!("[line {}] compiler error: {}", e.line, e.msg);
+            return InterpretStatus::CompilerError;
+        }
+    };
+    let mut compiler = Parser::new(buf.text(), runtime, module);
+    compiler.set_line_map(buf.line_map().clone());
 
     if let Some(closure_id) = compiler.compile() {
-        let mut vm = VM::new(&mut runtime);
+        runtime.get_module_mut(module).set_closure(closure_id);
+        // 計時 counts from here, not from when the runtime was made.
+        runtime.clock_mut().start();
+        let mut vm = VM::new(runtime);
         vm.setup_first_frame(closure_id);
         let ok = vm.run(mode);
         vm.free();
@@ -32,19 +58,48 @@ pub fn interpret(buf: &str, mode: VMMode) -> InterpretStatus {
 
 pub struct Runtime {
     interner: Interner,
-    closures: HashMap<FunId, Closure>,
+    functions: Vec<Function>,
+    closures: Vec<Closure>,
+    up_values: Vec<Captured>,
+    lists: Vec<Vec<Value>>,
+    objects: Vec<Object>,
     frames: Vec<CallFrame>,
     current_frame: *mut CallFrame,
+    modules: Vec<Module>,
+    search_paths: Vec<PathBuf>,
+    strict_types: bool,
+    natives: Vec<Native>,
+    // natives can be called by name from every module, unless a global hides them.
+    native_globals: HashMap<String, NativeId>,
+    books: HashMap<String, BookLoader>,
+    book_modules: HashMap<String, ModuleId>,
+    rng: Rng,
+    clock: Box<dyn Clock>,
 }
 
 impl Runtime {
     pub fn new() -> Self {
-        Self {
+        let mut runtime = Self {
             interner: Interner::new(),
-            closures: HashMap::new(),
+            functions: vec![],
+            closures: vec![],
+            up_values: vec![],
+            lists: vec![],
+            objects: vec![],
             frames: vec![],
             current_frame: std::ptr::null_mut(),
-        }
+            modules: vec![],
+            search_paths: vec![],
+            strict_types: false,
+            natives: vec![],
+            native_globals: HashMap::new(),
+            books: HashMap::new(),
+            book_modules: HashMap::new(),
+            rng: Rng::from_time(),
+            clock: Box::new(SystemClock::new()),
+        };
+        books::register(&mut runtime);
+        runtime
     }
     pub fn frames(&self) -> &Vec<CallFrame> {
         &self.frames
@@ -56,8 +111,7 @@ impl Runtime {
         unsafe { &mut *self.current_frame }
     }
     pub fn current_chunk(&self) -> &Chunk {
-        self.get_closure(&self.current_frame().closure_id())
-            .function()
+        self.closure_function(&self.current_frame().closure_id())
             .chunk()
     }
     pub fn begin_frame(
@@ -66,8 +120,7 @@ impl Runtime {
         slot_begin: usize,
         local_slot_begin: usize,
     ) -> u32 {
-        let closure = self.get_closure(&closure_idx);
-        let ip = closure.function().chunk().code().as_ptr();
+        let ip = self.closure_function(&closure_idx).chunk().code().as_ptr();
         let frame = CallFrame::new(ip, closure_idx, slot_begin, local_slot_begin);
 
         self.frames.push(frame);
@@ -87,14 +140,142 @@ impl Runtime {
         }
     }
 
-    pub fn add_closure(&mut self, fun: Function) -> u32 {
-        let closure = Closure::new(fun);
-        let id = self.closures.len() as u32;
-        self.closures.insert(id, closure);
+    pub fn add_function(&mut self, fun: Function) -> FunId {
+        self.functions.push(fun);
+        (self.functions.len() - 1) as FunId
+    }
+    pub fn get_function(&self, id: &FunId) -> &Function {
+        self.functions
+            .get(*id as usize)
+            .expect("Function not found.")
+    }
+    pub fn add_closure(&mut self, fun: FunId, up_values: Vec<UpValueId>) -> ClosureId {
+        let closure = Closure::new(fun, up_values);
+        self.closures.push(closure);
+        (self.closures.len() - 1) as ClosureId
+    }
+    pub fn get_closure(&self, id: &ClosureId) -> &Closure {
+        self.closures.get(*id as usize).expect("Closure not found.")
+    }
+    pub fn closure_function(&self, id: &ClosureId) -> &Function {
+        self.get_function(&self.get_closure(id).function())
+    }
+    pub fn add_up_value(&mut self, up_value: Captured) -> UpValueId {
+        self.up_values.push(up_value);
+        (self.up_values.len() - 1) as UpValueId
+    }
+    pub fn get_up_value(&self, id: &UpValueId) -> &Captured {
+        self.up_values
+            .get(*id as usize)
+            .expect("Up value not found.")
+    }
+    pub fn get_up_value_mut(&mut self, id: &UpValueId) -> &mut Captured {
+        self.up_values
+            .get_mut(*id as usize)
+            .expect("Up value not found.")
+    }
+
+    pub fn add_list(&mut self, list: Vec<Value>) -> ListId {
+        self.lists.push(list);
+        (self.lists.len() - 1) as ListId
+    }
+    pub fn get_list(&self, id: &ListId) -> &Vec<Value> {
+        self.lists.get(*id as usize).expect("List not found.")
+    }
+    pub fn get_list_mut(&mut self, id: &ListId) -> &mut Vec<Value> {
+        self.lists.get_mut(*id as usize).expect("List not found.")
+    }
+    pub fn add_object(&mut self, object: Object) -> ObjectId {
+        self.objects.push(object);
+        (self.objects.len() - 1) as ObjectId
+    }
+    pub fn get_object(&self, id: &ObjectId) -> &Object {
+        self.objects.get(*id as usize).expect("Object not found.")
+    }
+    pub fn get_object_mut(&mut self, id: &ObjectId) -> &mut Object {
+        self.objects
+            .get_mut(*id as usize)
+            .expect("Object not found.")
+    }
+
+    pub fn add_module(&mut self, module: Module) -> ModuleId {
+        self.modules.push(module);
+        (self.modules.len() - 1) as ModuleId
+    }
+    pub fn get_module(&self, id: ModuleId) -> &Module {
+        &self.modules[id as usize]
+    }
+    pub fn get_module_mut(&mut self, id: ModuleId) -> &mut Module {
+        &mut self.modules[id as usize]
+    }
+    pub fn module_id(&self, path: &PathBuf) -> Option<ModuleId> {
+        self.modules
+            .iter()
+            .position(|module| module.path() == Some(path))
+            .map(|id| id as ModuleId)
+    }
+    pub fn search_paths(&self) -> &Vec<PathBuf> {
+        &self.search_paths
+    }
+    pub fn add_search_path(&mut self, path: PathBuf) {
+        self.search_paths.push(path);
+    }
+    // 施「名」 calls `function` with the arguments, an Err fails or throws like any 術.
+    #[allow(dead_code)] // for hosts embedding the vm, wyw itself only ships the built-in books.
+    pub fn register(&mut self, name: &str, arity: usize, function: NativeFn) -> NativeId {
+        let id = self.add_native(Native::new(name, arity, function));
+        self.native_globals.insert(name.to_owned(), id);
         id
     }
-    pub fn get_closure(&self, id: &u32) -> &Closure {
-        self.closures.get(id).expect("Function not found.")
+    pub fn add_native(&mut self, native: Native) -> NativeId {
+        self.natives.push(native);
+        (self.natives.len() - 1) as NativeId
+    }
+    pub fn get_native(&self, id: &NativeId) -> &Native {
+        self.natives.get(*id as usize).expect("Native not found.")
+    }
+    pub fn native_global(&self, name: &str) -> Option<Value> {
+        self.native_globals.get(name).map(|id| Value::Native(*id))
+    }
+    // 吾嘗觀「「名」」之書 falls back to `loader` when no 名.wy is found.
+    pub fn add_book(&mut self, name: &str, loader: BookLoader) {
+        self.books.insert(name.to_owned(), loader);
+    }
+    // a built-in book is made into a module the first time it's imported.
+    pub fn load_book(&mut self, name: &str) -> Option<ModuleId> {
+        if let Some(module) = self.book_modules.get(name) {
+            return Some(*module);
+        }
+        let loader = *self.books.get(name)?;
+        let values = loader(self);
+        let module = self.add_module(Module::builtin(values));
+        self.book_modules.insert(name.to_owned(), module);
+        Some(module)
+    }
+    pub fn rng_mut(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+    // the same seed gives the same numbers from 渾沌經, without one they differ every run.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+    pub fn clock_mut(&mut self) -> &mut dyn Clock {
+        self.clock.as_mut()
+    }
+    // 曆法 reads the time from `clock`, tests pin it to a fixed time.
+    #[allow(dead_code)] // for hosts and tests, wyw always reads the SystemClock.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+    pub fn strict_types(&self) -> bool {
+        self.strict_types
+    }
+    // check values against the types they are declared with, see Parser::typed_expression.
+    pub fn set_strict_types(&mut self, strict_types: bool) {
+        self.strict_types = strict_types;
     }
 
     pub fn interner(&self) -> &Interner {
@@ -109,17 +290,37 @@ impl Runtime {
     }
 }
 
+// the ip to jump to, the local stack length and the handler count to restore.
+pub type JumpPoint = (*const u8, usize, usize);
+
+// where to go when an error is thrown inside 姑妄行此.
+#[derive(Clone, Copy)]
+pub struct Handler {
+    pub ip: *const u8,
+    pub stack_len: usize,
+    pub local_len: usize,
+    pub break_len: usize,
+    pub continue_len: usize,
+}
+
 pub struct CallFrame {
     ip: *const u8,
-    closure_id: FunId,
+    closure_id: ClosureId,
     slot_begin: usize,
     local_slot_begin: usize,
+    // loops belong to the function they are written in,
+    // so 乃止 can never jump into the bytecode of its caller.
+    break_points: Vec<JumpPoint>,
+    continue_points: Vec<JumpPoint>,
+    handlers: Vec<Handler>,
+    // the value produced by the latest statement, what 其 refers to.
+    prev: Value,
 }
 
 impl CallFrame {
     pub fn new(
         ip: *const u8,
-        closure_id: FunId,
+        closure_id: ClosureId,
         slot_begin: usize,
         local_slot_begin: usize,
     ) -> Self {
@@ -128,6 +329,10 @@ impl CallFrame {
             closure_id,
             slot_begin,
             local_slot_begin,
+            break_points: vec![],
+            continue_points: vec![],
+            handlers: vec![],
+            prev: Value::Nil,
         }
     }
     pub fn set_ip(&mut self, ip: *const u8) {
@@ -142,7 +347,7 @@ impl CallFrame {
     pub fn ip(&self) -> *const u8 {
         self.ip
     }
-    pub fn closure_id(&self) -> FunId {
+    pub fn closure_id(&self) -> ClosureId {
         self.closure_id
     }
     pub fn slot_begin(&self) -> usize {
@@ -151,4 +356,133 @@ impl CallFrame {
     pub fn local_slot_begin(&self) -> usize {
         self.local_slot_begin
     }
+    pub fn break_points_mut(&mut self) -> &mut Vec<JumpPoint> {
+        &mut self.break_points
+    }
+    pub fn continue_points_mut(&mut self) -> &mut Vec<JumpPoint> {
+        &mut self.continue_points
+    }
+    pub fn break_points(&self) -> &Vec<JumpPoint> {
+        &self.break_points
+    }
+    pub fn continue_points(&self) -> &Vec<JumpPoint> {
+        &self.continue_points
+    }
+    pub fn handlers(&self) -> &Vec<Handler> {
+        &self.handlers
+    }
+    pub fn handlers_mut(&mut self) -> &mut Vec<Handler> {
+        &mut self.handlers
+    }
+    pub fn prev(&self) -> Value {
+        self.prev
+    }
+    pub fn set_prev(&mut self, value: Value) {
+        self.prev = value;
+    }
+}
+
+// runs scripts in unit tests, 施「記」 keeps each number or string it's given.
+#[cfg(test)]
+pub mod testing {
+    use std::cell::RefCell;
+
+    use super::{interpret, InterpretStatus, Runtime};
+    use crate::{
+        object::NativeError,
+        value::Value,
+        vm::{VMMode, VM},
+    };
+
+    thread_local! {
+        static RECORDED: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+    }
+
+    fn record(vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+        let recorded = match args[0] {
+            Value::Number(num) => num.to_string(),
+            Value::String(id) => vm.runtime_mut().interner().lookup(id).to_owned(),
+            _ => return Err(NativeError::from("expect a number or a string.")),
+        };
+        RECORDED.with(|record| record.borrow_mut().push(recorded));
+        Ok(Value::Nil)
+    }
+
+    // `setup` can register more natives or pin the clock before the script runs.
+    pub fn run(buf: &str, setup: impl FnOnce(&mut Runtime)) -> (InterpretStatus, Vec<String>) {
+        RECORDED.with(|recorded| recorded.borrow_mut().clear());
+
+        let mut runtime = Runtime::new();
+        runtime.register("記", 1, record);
+        setup(&mut runtime);
+        let status = interpret(&mut runtime, buf, None, VMMode::Run);
+
+        (status, RECORDED.with(|recorded| recorded.borrow().clone()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{testing, InterpretStatus, Runtime};
+    use crate::{object::NativeError, value::Value, vm::VM};
+
+    fn subtract(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+        match (args[0], args[1]) {
+            (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a - b)),
+            _ => Err(NativeError::from("expect two numbers.")),
+        }
+    }
+
+    fn throw(_vm: &mut VM, args: &[Value]) -> Result<Value, NativeError> {
+        Err(NativeError::Throw(args[0]))
+    }
+
+    fn run(buf: &str) -> (InterpretStatus, Vec<String>) {
+        testing::run(buf, |runtime: &mut Runtime| {
+            runtime.register("減", 2, subtract);
+            runtime.register("擲", 1, throw);
+        })
+    }
+
+    #[test]
+    fn test_native_call() {
+        let (status, recorded) = run("施「減」於十於三名之曰「甲」
+            施「記」於「甲」
+            以十以三施「減」施「記」於其
+            吾有一術名之曰「轉」欲行是術必先得一術曰「法」乃行是術曰
+                施「法」於一於二名之曰「乙」
+                乃得「乙」
+            是謂「轉」之術也
+            施「轉」於「減」施「記」於其");
+
+        assert_eq!(status, InterpretStatus::Ok);
+        assert_eq!(recorded, vec!["7", "7", "-1"]);
+    }
+
+    #[test]
+    fn test_native_error() {
+        let (status, recorded) = run("姑妄行此
+                施「減」於「「甲」」於三
+            如事不諧
+                豈「「expect two numbers.」」之禍歟
+                    施「記」於一
+            乃作罷
+            施「減」於一");
+
+        assert_eq!(status, InterpretStatus::RuntimeError);
+        assert_eq!(recorded, vec!["1"]);
+    }
+
+    #[test]
+    fn test_native_throw() {
+        let (status, recorded) = run("姑妄行此
+                施「擲」於四十二
+            如事不諧
+                不知何禍歟名之曰「禍」
+                    施「記」於「禍」
+            乃作罷");
+
+        assert_eq!(status, InterpretStatus::Ok);
+        assert_eq!(recorded, vec!["42"]);
+    }
 }
@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+
+// every char remembers the line it came from in the user's file.
+type Text = Vec<(char, usize)>;
+
+// expanding a macro may produce another macro call, but not forever.
+const MAX_EXPANSION_PASSES: usize = 64;
+
+pub struct Expanded {
+    text: String,
+    line_map: Vec<usize>,
+}
+
+impl Expanded {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    // the original line of each line in the expanded text.
+    pub fn line_map(&self) -> &Vec<usize> {
+        &self.line_map
+    }
+}
+
+pub struct MacroError {
+    pub line: usize,
+    pub msg: String,
+}
+
+enum Piece {
+    Literal(Vec<char>),
+    Hole(String),
+}
+
+// 或云「「書「甲」焉」」。蓋謂「「吾有一言。曰「甲」。書之」」。
+struct Macro {
+    pattern: Vec<Piece>,
+    replacement: Vec<char>,
+}
+
+impl Macro {
+    fn new(pattern: &[char], replacement: &[char]) -> Self {
+        let mut pieces = vec![];
+        let mut literal = vec![];
+        let mut i = 0;
+
+        while i < pattern.len() {
+            if let Some((name, end)) = read_hole(pattern, i) {
+                if !literal.is_empty() {
+                    pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                }
+                pieces.push(Piece::Hole(name));
+                i = end;
+            } else {
+                literal.push(pattern[i]);
+                i += 1;
+            }
+        }
+
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+
+        Self {
+            pattern: pieces,
+            replacement: replacement.to_vec(),
+        }
+    }
+    fn match_at(&self, text: &Text, start: usize) -> Option<(usize, HashMap<String, Text>)> {
+        let mut captures = HashMap::new();
+        match_pieces(&self.pattern, text, start, &mut captures).map(|end| (end, captures))
+    }
+    // the replacement takes the line of the call, captured text keeps its own.
+    fn expand(&self, captures: &HashMap<String, Text>, line: usize) -> Text {
+        let mut result = vec![];
+        let mut i = 0;
+
+        while i < self.replacement.len() {
+            if let Some((name, end)) = read_hole(&self.replacement, i) {
+                if let Some(captured) = captures.get(&name) {
+                    result.extend(captured.iter().cloned());
+                    i = end;
+                    continue;
+                }
+            }
+
+            result.push((self.replacement[i], line));
+            i += 1;
+        }
+
+        result
+    }
+}
+
+pub fn expand(buf: &str) -> Result<Expanded, MacroError> {
+    if !buf.contains("或云") {
+        return Ok(Expanded {
+            text: buf.to_owned(),
+            line_map: vec![],
+        });
+    }
+
+    let mut line = 1;
+    let mut text = Text::new();
+    for ch in buf.chars() {
+        text.push((ch, line));
+        if ch == '\n' {
+            line += 1;
+        }
+    }
+
+    let (mut text, macros) = collect_macros(&text)?;
+
+    for _ in 0..MAX_EXPANSION_PASSES {
+        let (expanded, changed) = expand_once(&text, &macros);
+        text = expanded;
+
+        if !changed {
+            return Ok(Expanded {
+                text: text.iter().map(|(ch, _)| ch).collect(),
+                line_map: line_map(&text),
+            });
+        }
+    }
+
+    Err(MacroError {
+        line: text.first().map(|(_, line)| *line).unwrap_or(1),
+        msg: String::from("macro expands too many times."),
+    })
+}
+
+// take the definitions out of the source, but keep their newlines so the lines after them don't move.
+fn collect_macros(text: &Text) -> Result<(Text, Vec<Macro>), MacroError> {
+    let mut rest = Text::new();
+    let mut macros = vec![];
+    let mut i = 0;
+
+    while i < text.len() {
+        if let Some(end) = skip_literal(text, i) {
+            rest.extend(text[i..end].iter().cloned());
+            i = end;
+            continue;
+        }
+        if !starts_with(text, i, "或云") {
+            rest.push(text[i]);
+            i += 1;
+            continue;
+        }
+
+        let line = text[i].1;
+        let error = |msg: &str| MacroError {
+            line,
+            msg: String::from(msg),
+        };
+
+        let (pattern, end) = read_quoted(text, skip_trivia(text, i + 2))
+            .ok_or_else(|| error("expect a quoted pattern after '或云'."))?;
+        let next = skip_trivia(text, end);
+        if !starts_with(text, next, "蓋謂") {
+            return Err(error("expect '蓋謂' after the pattern of '或云'."));
+        }
+        let (replacement, end) = read_quoted(text, skip_trivia(text, next + 2))
+            .ok_or_else(|| error("expect a quoted replacement after '蓋謂'."))?;
+
+        rest.extend(text[i..end].iter().filter(|(ch, _)| *ch == '\n'));
+        macros.push(Macro::new(&pattern, &replacement));
+        i = end;
+    }
+
+    Ok((rest, macros))
+}
+
+fn expand_once(text: &Text, macros: &[Macro]) -> (Text, bool) {
+    let mut result = Text::new();
+    let mut changed = false;
+    let mut i = 0;
+
+    while i < text.len() {
+        // never expand inside a string literal or a comment.
+        if let Some(end) = skip_literal(text, i) {
+            result.extend(text[i..end].iter().cloned());
+            i = end;
+            continue;
+        }
+
+        let matched = macros.iter().find_map(|m| {
+            m.match_at(text, i)
+                .map(|(end, captures)| (m, end, captures))
+        });
+
+        match matched {
+            Some((m, end, captures)) => {
+                result.extend(m.expand(&captures, text[i].1));
+                changed = true;
+                i = end;
+            }
+            None => {
+                result.push(text[i]);
+                i += 1;
+            }
+        }
+    }
+
+    (result, changed)
+}
+
+fn match_pieces(
+    pieces: &[Piece],
+    text: &Text,
+    start: usize,
+    captures: &mut HashMap<String, Text>,
+) -> Option<usize> {
+    let piece = match pieces.first() {
+        Some(piece) => piece,
+        None => return Some(start),
+    };
+
+    match piece {
+        Piece::Literal(literal) => {
+            let matched = literal
+                .iter()
+                .enumerate()
+                .all(|(offset, ch)| text.get(start + offset).map(|(c, _)| c) == Some(ch));
+
+            if matched {
+                match_pieces(&pieces[1..], text, start + literal.len(), captures)
+            } else {
+                None
+            }
+        }
+        // the same placeholder used twice has to match the same text.
+        Piece::Hole(name) if captures.contains_key(name) => {
+            let captured = &captures[name];
+            let matched = captured
+                .iter()
+                .enumerate()
+                .all(|(offset, (ch, _))| text.get(start + offset).map(|(c, _)| c) == Some(ch));
+
+            if matched {
+                match_pieces(&pieces[1..], text, start + captured.len(), captures)
+            } else {
+                None
+            }
+        }
+        // as short as possible, but never across a line or half of a 「」.
+        Piece::Hole(name) => {
+            let mut end = start + 1;
+            while end <= text.len() && text[end - 1].0 != '\n' {
+                let captured = text[start..end].to_vec();
+                if is_balanced(&captured) {
+                    captures.insert(name.clone(), captured);
+                    if let Some(end) = match_pieces(&pieces[1..], text, end, captures) {
+                        return Some(end);
+                    }
+                    captures.remove(name);
+                }
+                end += 1;
+            }
+
+            None
+        }
+    }
+}
+
+fn is_balanced(text: &[(char, usize)]) -> bool {
+    let count = |target: char| text.iter().filter(|(ch, _)| *ch == target).count();
+
+    count('「') == count('」') && count('『') == count('』')
+}
+
+// 「甲」 in a pattern or replacement, but not the 「「 of a string.
+fn read_hole(chars: &[char], start: usize) -> Option<(String, usize)> {
+    if chars.get(start) != Some(&'「') || chars.get(start + 1) == Some(&'「') {
+        return None;
+    }
+
+    let close = chars[start + 1..].iter().position(|ch| *ch == '」')? + start + 1;
+    let name = chars[start + 1..close].iter().collect::<String>();
+    if name.is_empty() || name.contains('「') {
+        return None;
+    }
+
+    Some((name, close + 1))
+}
+
+// 「「…」」 or 『…』, placeholders inside are fine, so 「「書「甲」」」 ends at the last 」.
+fn read_quoted(text: &Text, start: usize) -> Option<(Vec<char>, usize)> {
+    let ch = |i: usize| text.get(i).map(|(ch, _)| *ch);
+
+    if ch(start) == Some('「') && ch(start + 1) == Some('「') {
+        let mut close = start + 2;
+        while close + 1 < text.len() {
+            if ch(close) == Some('」') && ch(close + 1) == Some('」') {
+                while ch(close + 2) == Some('」') {
+                    close += 1;
+                }
+                let content = text[start + 2..close].iter().map(|(ch, _)| *ch).collect();
+                return Some((content, close + 2));
+            }
+            close += 1;
+        }
+        None
+    } else if ch(start) == Some('『') {
+        let close = text[start..].iter().position(|(ch, _)| *ch == '』')? + start;
+        let content = text[start + 1..close].iter().map(|(ch, _)| *ch).collect();
+        Some((content, close + 1))
+    } else {
+        None
+    }
+}
+
+// the end of a string, or of a 注曰 comment with its string.
+fn skip_literal(text: &Text, start: usize) -> Option<usize> {
+    if let Some((_, end)) = read_quoted(text, start) {
+        return Some(end);
+    }
+    if ["注曰", "疏曰", "批曰"]
+        .iter()
+        .any(|keyword| starts_with(text, start, keyword))
+    {
+        return read_quoted(text, skip_trivia(text, start + 2)).map(|(_, end)| end);
+    }
+
+    None
+}
+
+fn starts_with(text: &Text, start: usize, keyword: &str) -> bool {
+    keyword
+        .chars()
+        .enumerate()
+        .all(|(offset, ch)| text.get(start + offset).map(|(c, _)| *c) == Some(ch))
+}
+
+fn skip_trivia(text: &Text, mut start: usize) -> usize {
+    while let Some((' ' | '\t' | '\r' | '\n' | '。' | '、', _)) = text.get(start) {
+        start += 1;
+    }
+
+    start
+}
+
+fn line_map(text: &Text) -> Vec<usize> {
+    let mut map = vec![];
+    let mut line_start = true;
+    let mut last = 1;
+
+    for (ch, line) in text {
+        if line_start {
+            map.push(*line);
+            last = *line;
+            line_start = false;
+        }
+        if *ch == '\n' {
+            line_start = true;
+        }
+    }
+
+    if line_start {
+        map.push(last);
+    }
+
+    map
+}
+
+#[cfg(test)]
+mod test {
+    use super::expand;
+
+    #[test]
+    fn test_expand_keeps_lines() {
+        let expanded = expand("或云「「書「甲」焉」」\n蓋謂「「曰「甲」\n書之」」\n書三焉\n書之")
+            .ok()
+            .unwrap();
+
+        assert_eq!(expanded.text(), "\n\n\n曰三\n書之\n書之");
+        assert_eq!(expanded.line_map(), &vec![1, 2, 3, 4, 4, 5]);
+    }
+
+    #[test]
+    fn test_expand_without_replacement() {
+        let error = expand("\n或云「「書「甲」焉」」").err().unwrap();
+
+        assert_eq!(error.line, 2);
+        assert_eq!(error.msg, "expect '蓋謂' after the pattern of '或云'.");
+    }
+
+    #[test]
+    fn test_expand_skips_strings_and_comments() {
+        let buf = "注曰「「或云之法」」
+吾有一言曰「「或云「「甲」」蓋謂「「乙」」」」書之
+或云「「甲焉」」蓋謂「「乙」」
+批曰「「甲焉」」甲焉";
+        let expanded = expand(buf).ok().unwrap();
+
+        assert_eq!(
+            expanded.text(),
+            "注曰「「或云之法」」
+吾有一言曰「「或云「「甲」」蓋謂「「乙」」」」書之
+
+批曰「「甲焉」」乙"
+        );
+    }
+}
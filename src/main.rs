@@ -1,10 +1,13 @@
+mod books;
 mod chunk;
 mod compiler;
 mod convert;
 mod debug;
 mod interner;
 mod interpreter;
+mod macros;
 mod memory;
+mod module;
 mod object;
 mod opcode;
 mod statements;
@@ -12,10 +15,10 @@ mod tokenize;
 mod value;
 mod vm;
 
-use std::{fs::File, io::Read};
+use std::{fs::File, io::Read, path::PathBuf};
 
 use clap::Parser;
-use interpreter::interpret;
+use interpreter::{interpret, Runtime};
 use vm::VMMode;
 
 #[derive(Parser)]
@@ -24,6 +27,15 @@ struct Cli {
     path: String,
     #[arg(short, long, default_value_t = false)]
     debug: bool,
+    /// Directories to look for books imported by 吾嘗觀, after the script's own directory.
+    #[arg(short = 'I', long = "include")]
+    include: Vec<PathBuf>,
+    /// Check values against the types they are declared with, like 吾有一數.
+    #[arg(long, default_value_t = false)]
+    strict_types: bool,
+    /// Seed the random numbers of 渾沌經, so every run gets the same ones.
+    #[arg(long)]
+    seed: Option<u64>,
 }
 
 fn main() {
@@ -39,7 +51,16 @@ fn main() {
                         false => VMMode::Run,
                     };
 
-                    interpret(&buf, mode);
+                    let mut runtime = Runtime::new();
+                    for path in cli.include {
+                        runtime.add_search_path(path);
+                    }
+                    runtime.set_strict_types(cli.strict_types);
+                    if let Some(seed) = cli.seed {
+                        runtime.set_seed(seed);
+                    }
+
+                    interpret(&mut runtime, &buf, Some(PathBuf::from(&s)), mode);
                 }
                 Err(e) => {
                     eprintln!("{}", &s);
@@ -57,6 +78,7 @@ fn main() {
 #[test]
 fn run() {
     interpret(
+        &mut Runtime::new(),
         "吾有一術名之曰「階乘」欲行是術必先得一數曰「甲」乃行是術曰
         若「甲」等於一者。
             乃得「甲」
@@ -69,6 +91,7 @@ fn run() {
     是謂「階乘」之術也
     
     施「階乘」於五書之",
+        None,
         VMMode::Run,
     );
 }
@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+use crate::{object::ClosureId, value::Value};
+
+pub type ModuleId = u32;
+
+// a .wy file loaded by 吾嘗觀「「書」」之書, the entry script is a module too.
+// built-in books like 算經 have no file, their `values` become globals when imported.
+pub struct Module {
+    path: Option<PathBuf>,
+    closure: Option<ClosureId>,
+    exports: Vec<String>,
+    values: Option<Vec<(String, Value)>>,
+    // the book couldn't be read or compiled, the error is already reported.
+    failed: bool,
+}
+
+impl Module {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            closure: None,
+            exports: vec![],
+            values: None,
+            failed: false,
+        }
+    }
+    pub fn builtin(values: Vec<(String, Value)>) -> Self {
+        Self {
+            path: None,
+            closure: None,
+            exports: values.iter().map(|(name, _)| name.clone()).collect(),
+            values: Some(values),
+            failed: false,
+        }
+    }
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+    pub fn dir(&self) -> Option<PathBuf> {
+        self.path
+            .as_ref()
+            .and_then(|path| path.parent())
+            .map(|dir| dir.to_path_buf())
+    }
+    pub fn closure(&self) -> Option<ClosureId> {
+        self.closure
+    }
+    pub fn set_closure(&mut self, closure: ClosureId) {
+        self.closure = Some(closure);
+    }
+    // a module has no closure until it's fully compiled,
+    // seeing it again before that means it imports itself.
+    pub fn is_loading(&self) -> bool {
+        self.closure.is_none() && self.values.is_none() && !self.failed
+    }
+    pub fn is_failed(&self) -> bool {
+        self.failed
+    }
+    pub fn set_failed(&mut self) {
+        self.failed = true;
+    }
+    pub fn values(&self) -> Option<&Vec<(String, Value)>> {
+        self.values.as_ref()
+    }
+    pub fn exports(&self) -> &Vec<String> {
+        &self.exports
+    }
+    pub fn add_export(&mut self, name: String) {
+        if !self.exports.contains(&name) {
+            self.exports.push(name);
+        }
+    }
+}
+
+// 「「書」」 is looked up as 書.wy, next to the importing file first, then in the search paths.
+pub fn find_module(name: &str, dir: Option<PathBuf>, search_paths: &[PathBuf]) -> Option<PathBuf> {
+    let file = format!("{}.wy", name);
+
+    dir.iter()
+        .chain(search_paths.iter())
+        .map(|dir| dir.join(&file))
+        .find(|path| path.is_file())
+        .and_then(|path| path.canonicalize().ok())
+}
@@ -1,12 +1,26 @@
-use crate::chunk::Chunk;
+use crate::{
+    chunk::Chunk,
+    interner::StrId,
+    module::ModuleId,
+    value::{Kind, Value},
+    vm::VM,
+};
 
 pub type FunId = u32;
 pub type ClosureId = u32;
+pub type ListId = u32;
+pub type ObjectId = u32;
+pub type UpValueId = u32;
+pub type NativeId = u32;
 
 pub struct Function {
     arity: usize,
     chunk: Chunk,
     name: String,
+    up_value_count: usize,
+    // the module whose globals this function reads and writes.
+    module: ModuleId,
+    param_kinds: Vec<Kind>,
 }
 
 impl Function {
@@ -15,6 +29,9 @@ impl Function {
             arity: usize::default(),
             chunk: Chunk::new(),
             name: String::default(),
+            up_value_count: 0,
+            module: 0,
+            param_kinds: vec![],
         }
     }
     pub fn name(&self) -> &str {
@@ -35,32 +52,147 @@ impl Function {
     pub fn arity(&self) -> usize {
         self.arity
     }
+    pub fn add_param_kind(&mut self, kind: Kind) {
+        self.param_kinds.push(kind);
+    }
+    pub fn param_kinds(&self) -> &Vec<Kind> {
+        &self.param_kinds
+    }
+    // 加法(數, 數), how the 術 is printed.
+    pub fn signature(&self) -> String {
+        let kinds = self
+            .param_kinds
+            .iter()
+            .map(|kind| kind.name())
+            .collect::<Vec<&str>>();
+
+        format!("{}({})", self.name, kinds.join(", "))
+    }
+    pub fn set_up_value_count(&mut self, count: usize) {
+        self.up_value_count = count;
+    }
+    pub fn up_value_count(&self) -> usize {
+        self.up_value_count
+    }
+    pub fn set_module(&mut self, module: ModuleId) {
+        self.module = module;
+    }
+    pub fn module(&self) -> ModuleId {
+        self.module
+    }
 }
 
+// where the compiler finds a captured variable,
+// a local of the enclosing function or one of its up values.
+#[derive(Clone, Copy, PartialEq)]
 pub struct UpValue {
-    pub index: usize,
+    pub index: u32,
     pub is_local: bool,
 }
 
+// a captured variable at runtime, it points into the local stack
+// until the variable goes out of scope, then it holds the value itself.
+#[derive(Clone, Copy)]
+pub enum Captured {
+    Open(usize),
+    Closed(Value),
+    // closed after 今不復存矣 deleted it, reading it is an error.
+    Deleted(StrId),
+}
+
 pub struct Closure {
-    function: Function,
-    up_values: Vec<UpValue>,
+    function: FunId,
+    up_values: Vec<UpValueId>,
 }
 
 impl Closure {
-    pub fn new(function: Function) -> Self {
+    pub fn new(function: FunId, up_values: Vec<UpValueId>) -> Self {
         Self {
             function,
-            up_values: Vec::new(),
+            up_values,
         }
     }
-    pub fn function(&self) -> &Function {
-        &self.function
+    pub fn function(&self) -> FunId {
+        self.function
     }
-    pub fn up_values(&self) -> &Vec<UpValue> {
+    pub fn up_values(&self) -> &Vec<UpValueId> {
         &self.up_values
     }
-    pub fn up_values_mut(&mut self) -> &mut Vec<UpValue> {
-        &mut self.up_values
+}
+
+// a 術 written in rust, it gets the arguments in the order they are given.
+pub type NativeFn = fn(&mut VM, &[Value]) -> Result<Value, NativeError>;
+
+// how a native 術 fails, both can be caught by 姑妄行此.
+pub enum NativeError {
+    // a runtime error like the vm's own, 豈「「message」」之禍歟 catches it.
+    Message(String),
+    // any value, thrown like 嗚呼.
+    #[allow(dead_code)] // none of the built-in books throw, hosts embedding the vm do.
+    Throw(Value),
+}
+
+impl From<String> for NativeError {
+    fn from(msg: String) -> Self {
+        NativeError::Message(msg)
+    }
+}
+
+impl From<&str> for NativeError {
+    fn from(msg: &str) -> Self {
+        NativeError::Message(msg.to_owned())
+    }
+}
+
+pub struct Native {
+    name: String,
+    arity: usize,
+    function: NativeFn,
+}
+
+impl Native {
+    pub fn new(name: &str, arity: usize, function: NativeFn) -> Self {
+        Self {
+            name: name.to_owned(),
+            arity,
+            function,
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+    pub fn function(&self) -> NativeFn {
+        self.function
+    }
+}
+
+pub struct Object {
+    properties: Vec<(StrId, Value)>,
+}
+
+impl Object {
+    pub fn new() -> Self {
+        Self { properties: vec![] }
+    }
+    pub fn properties(&self) -> &Vec<(StrId, Value)> {
+        &self.properties
+    }
+    pub fn get(&self, key: StrId) -> Option<&Value> {
+        self.properties
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+    pub fn remove(&mut self, key: StrId) {
+        self.properties.retain(|(k, _)| *k != key);
+    }
+    pub fn set(&mut self, key: StrId, value: Value) {
+        match self.properties.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, old)) => *old = value,
+            None => self.properties.push((key, value)),
+        }
     }
 }
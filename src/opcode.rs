@@ -10,6 +10,8 @@ pub const TRUE: u8 = 8;
 pub const FALSE: u8 = 9;
 pub const PRINT: u8 = 10;
 pub const EQUAL_EQUAL: u8 = 11;
+pub const DIVIDE: u8 = 12;
+pub const REMAINDER: u8 = 13;
 pub const GREATER: u8 = 15;
 pub const LESS: u8 = 16;
 pub const DEFINE_GLOBAL: u8 = 17;
@@ -29,7 +31,35 @@ pub const LOOP: u8 = 29;
 pub const BREAK: u8 = 30;
 pub const RECORD_BREAK: u8 = 31;
 pub const DISCARD_BREAK: u8 = 32;
+pub const CONTINUE: u8 = 43;
+pub const RECORD_CONTINUE: u8 = 44;
+pub const DISCARD_CONTINUE: u8 = 45;
 
 pub const CALL: u8 = 33;
 pub const NIL: u8 = 34;
 pub const CLOSURE: u8 = 35;
+
+pub const LIST: u8 = 36;
+pub const APPEND: u8 = 37;
+pub const INDEX: u8 = 38;
+pub const LENGTH: u8 = 39;
+pub const REST: u8 = 40;
+pub const OBJECT: u8 = 41;
+pub const SET_INDEX: u8 = 42;
+pub const TRY: u8 = 46;
+pub const END_TRY: u8 = 47;
+pub const THROW: u8 = 48;
+pub const GET_UPVALUE: u8 = 49;
+pub const SET_UPVALUE: u8 = 50;
+pub const IMPORT: u8 = 51;
+pub const IMPORT_NAME: u8 = 52;
+pub const CALL_STACKED: u8 = 53;
+pub const REVERSE: u8 = 54;
+pub const CLEAR: u8 = 55;
+pub const SET_PREV: u8 = 56;
+pub const GET_PREV: u8 = 57;
+pub const TAKE_PREV: u8 = 58;
+pub const CHECK_TYPE: u8 = 59;
+pub const DELETE_GLOBAL: u8 = 60;
+pub const DELETE_LOCAL: u8 = 61;
+pub const DELETE_INDEX: u8 = 62;
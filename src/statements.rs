@@ -3,9 +3,12 @@ use crate::{
     convert::hanzi2num::hanzi2num,
     opcode::{self},
     tokenize::token::Token,
-    value::Value,
+    value::{Kind, Value},
 };
 
+// parameters are defined by DEFINE_LOCAL with a one byte offset.
+const MAX_PARAMETERS: usize = u8::MAX as usize;
+
 pub fn unary_statement(parser: &mut Parser, token: &Token) {
     parser.advance();
 
@@ -23,6 +26,15 @@ pub fn print_statement(parser: &mut Parser) {
     parser.advance();
 
     parser.emit_u8(opcode::PRINT);
+    parser.consume_value();
+}
+
+// 噫 throws away every value produced by current function.
+pub fn clear_statement(parser: &mut Parser) {
+    parser.advance();
+
+    parser.emit_u8(opcode::CLEAR);
+    parser.consume_value();
 }
 
 pub fn expression_statement(parser: &mut Parser) {
@@ -88,6 +100,9 @@ pub fn binary_statement(parser: &mut Parser, token: &Token) {
         Token::Plus => Some(opcode::ADD),
         Token::Minus => Some(opcode::SUBTRACT),
         Token::Star => Some(opcode::MULTIPLY),
+        // 除「甲」以「乙」。所餘幾何。 asks for the remainder instead of the quotient.
+        Token::Slash if parser.is_match(Token::Remainder) => Some(opcode::REMAINDER),
+        Token::Slash => Some(opcode::DIVIDE),
         _ => None,
     }
     .map(|op_code| parser.emit_u8(op_code));
@@ -107,21 +122,29 @@ pub fn normal_declaration<'a>(parser: &'a mut Parser, buf: &'a str) {
         None => None,
     };
 
-    // skip strict type judgment for now
-    parser.advance();
+    parser.consume(Token::Type, "expect a type in declaration.");
+    let kind = parser.get_prev_token_string();
+    let declared = Kind::from_name(&kind);
 
     if let Some(num) = num {
         for _ in 0..num {
-            parser.consume(Token::Is, "expect '曰' in declaration.");
-            parser.expression()
+            if parser.is_match(Token::Is) {
+                parser.typed_expression(declared)
+            } else {
+                default_value(parser, &kind);
+                parser.push_operands(1);
+            }
         }
 
         let mut offset = (num - 1) as u8;
         let mut has_define_statement = false;
+        let mut last_name = None;
         while parser.is_match(Token::NameIs) {
             // 名之曰
             has_define_statement = true;
             let global = parse_variable(parser, "Expect variable name.");
+            last_name = Some(parser.get_prev_token_string());
+            parser.declare_kind(declared);
             if let Some(global) = global {
                 parser.emit_u8(opcode::DEFINE_GLOBAL);
                 parser.emit_u32(global);
@@ -137,6 +160,10 @@ pub fn normal_declaration<'a>(parser: &'a mut Parser, buf: &'a str) {
             }
         }
 
+        if parser.check(Token::ObjectBegin) {
+            object_statement(parser, last_name);
+        }
+
         if has_define_statement {
             if offset != 0 {
                 parser.error("expect named all variable you declared.")
@@ -145,19 +172,66 @@ pub fn normal_declaration<'a>(parser: &'a mut Parser, buf: &'a str) {
                     parser.emit_u8(opcode::POP);
                 }
             }
+        } else {
+            // 吾有三數曰一曰二曰三 without names, 其 is the last one.
+            parser.produce_value();
         }
     } else {
         parser.error("expect a number in declaration.");
     }
 }
 
+// 吾有一列 has no initial value, so every type needs a default one.
+fn default_value(parser: &mut Parser, kind: &str) {
+    match kind {
+        "數" => parser.emit_constant(Value::Number(0.0)),
+        "言" => {
+            let value = parser.string_value("");
+            parser.emit_constant(value)
+        }
+        "爻" => parser.emit_u8(opcode::FALSE),
+        "列" => parser.emit_u8(opcode::LIST),
+        "物" => parser.emit_u8(opcode::OBJECT),
+        _ => parser.emit_u8(opcode::NIL),
+    }
+}
+
+// 其物如是。物之「「鍵」」者。數曰三。是謂「甲」之物也。
+// fill properties into the object at the top of stack, it's the last one named.
+fn object_statement(parser: &mut Parser, name: Option<String>) {
+    parser.advance();
+
+    while parser.is_match(Token::ObjectProperty) {
+        parser.consume(Token::String, "expect a key in object property.");
+        let key = parser.str_to_value();
+        parser.emit_constant(key);
+        parser.consume(Token::Conjunction, "expect '者' in object property.");
+        parser.consume(Token::Type, "expect a type in object property.");
+        parser.consume(Token::Is, "expect '曰' in object property.");
+        parser.expression();
+        parser.emit_u8(opcode::SET_INDEX);
+        parser.pop_operands(1);
+    }
+
+    parser.consume(Token::FunctionEnd1, "expect '是謂' in object end.");
+    parser.consume(Token::Identifier, "expect identifier in object end.");
+    let end_name = parser.get_prev_token_string();
+    if let Some(name) = name {
+        if name != end_name {
+            parser.error(format!("expect 是謂「{}」 but got 「{}」.", name, end_name).as_str());
+        }
+    }
+    parser.consume(Token::ObjectEnd, "expect '之物也' in object end.");
+}
+
 pub fn short_declaration<'a>(parser: &mut Parser) {
-    parser.advance(); // 有
+    parser.advance(); // 有數
+    let declared = Kind::from_name(&parser.get_prev_token_string());
 
-    // skip strict type judgment for now
-    parser.expression();
+    parser.typed_expression(declared);
 
-    name_is_statement(parser);
+    parser.advance(); // 名之曰
+    name_top(parser, declared);
 }
 
 fn parse_variable(parser: &mut Parser, error: &str) -> Option<u32> {
@@ -167,6 +241,7 @@ fn parse_variable(parser: &mut Parser, error: &str) -> Option<u32> {
         return None;
     }
 
+    parser.export_global(parser.get_prev_token_string());
     parser.identifier_constant()
 }
 
@@ -182,23 +257,72 @@ pub fn assign_statement<'a>(parser: &'a mut Parser) {
     parser.advance(); // skip '昔之'
     parser.advance(); // skip 'variable name'
 
-    let arg = parser.resolve_local(parser.get_prev_token_string());
+    if parser.check(Token::Of) {
+        // 昔之「甲」之「「鍵」」者今三是矣
+        parser.named_variable();
+        parser.advance();
+        if !parser.index_key() {
+            return;
+        }
+        parser.push_operands(2);
+        parser.consume(Token::Conjunction, "expect '者' in assign statement");
+        if parser.is_match(Token::Delete) {
+            parser.emit_u8(opcode::DELETE_INDEX);
+            return;
+        }
+        parser.consume(Token::AssignTo, "expect '今' in assign statement.");
+        parser.expression();
+        parser.consume(Token::Sure, "expect '是矣' in assign statement.");
+        parser.emit_u8(opcode::SET_INDEX);
+        parser.emit_u8(opcode::POP);
+        return;
+    }
 
+    let name = parser.get_prev_token_string();
+    let kind = parser.variable_kind(&name);
+    let arg = parser.resolve_local(parser.get_prev_token_string());
     let (x, y) = match arg {
         Some(arg) => (opcode::SET_LOCAL, arg),
-        None => (opcode::SET_GLOBAL, parser.identifier_constant().unwrap()),
+        None => match parser.resolve_up_value(parser.get_prev_token_string()) {
+            Some(arg) => (opcode::SET_UPVALUE, arg),
+            None => (opcode::SET_GLOBAL, parser.identifier_constant().unwrap()),
+        },
     };
 
     parser.consume(Token::Conjunction, "expect '者' in assign statement");
+    if parser.is_match(Token::Delete) {
+        delete_variable(parser, x, y, &name);
+        return;
+    }
     parser.consume(Token::AssignTo, "expect '今' in assign statement.");
-    parser.expression();
+    parser.typed_expression(kind);
     parser.consume(Token::Sure, "expect '是矣' in assign statement.");
     parser.emit_u8(x);
     parser.emit_u32(y);
 }
 
+// 昔之「甲」者今不復存矣, reading 「甲」 again is an error until it's assigned.
+fn delete_variable(parser: &mut Parser, set_op: u8, arg: u32, name: &str) {
+    match set_op {
+        opcode::SET_LOCAL => {
+            // the slot stays, the vm needs the name to report reading it.
+            let value = parser.string_value(name);
+            let constant = parser.make_constant(value).unwrap_or(0);
+            parser.emit_bytes(opcode::DELETE_LOCAL, arg);
+            parser.emit_u32(constant);
+        }
+        opcode::SET_UPVALUE => {
+            parser.error("cannot delete a variable of the enclosing 術.");
+        }
+        _ => parser.emit_bytes(opcode::DELETE_GLOBAL, arg),
+    }
+}
+
+// we can't tell which branch or how many rounds ran at compile time,
+// so 其 only gets a copy of values produced across the bounds of a block.
 pub fn block_statement<'a, const N: usize>(parser: &'a mut Parser, stop_before_tokens: [Token; N]) {
     parser.begin_scope();
+    parser.consume_value();
     let preset_stop_tokens = [Token::YunYun, Token::Ye];
 
     while parser.check_not_in_vec(&stop_before_tokens)
@@ -215,12 +339,18 @@ pub fn block_statement<'a, const N: usize>(parser: &'a mut Parser, stop_before_t
         parser.error("expect end token in block statement.")
     }
 
+    parser.consume_value();
     parser.end_scope();
 }
 
 pub fn name_is_statement<'a>(parser: &'a mut Parser) {
     parser.advance();
+    name_top(parser, None);
+}
+
+fn name_top(parser: &mut Parser, kind: Option<Kind>) {
     let global = parse_variable(parser, "Expect variable name.");
+    parser.declare_kind(kind);
     if let Some(global) = global {
         parser.emit_u8(opcode::DEFINE_GLOBAL);
         parser.emit_u32(global);
@@ -232,26 +362,49 @@ pub fn name_is_statement<'a>(parser: &'a mut Parser) {
     // this is different from normal declaration, because it always choose top of stack.
     parser.emit_u8(0);
     parser.emit_u8(opcode::POP);
+    parser.consume_value();
 }
 
 pub fn if_statement<'a>(parser: &'a mut Parser) {
-    parser.advance();
-    expression_statement(parser);
-    parser.consume(Token::Conjunction, "expect '者'");
-    let then_jump = parser.emit_jump(opcode::JUMP_IF_FALSE);
-    parser.emit_u8(opcode::POP);
-    block_statement(parser, [Token::Else]);
+    let mut exit_jumps = vec![];
 
-    let else_jump = parser.emit_jump(opcode::JUMP);
-    parser.patch_jump(then_jump);
-    parser.emit_u8(opcode::POP);
+    // 若…者 and every following 或若…者 share the same shape,
+    // they all jump to the end of the whole chain when its block finished.
+    loop {
+        parser.advance();
+        match parser.previous().get_value() {
+            // 若其然者 and 若其不然者 judge the value just computed on top of stack.
+            Token::IfTrue => parser.consume_value(),
+            Token::IfFalse => {
+                parser.emit_u8(opcode::INVERT);
+                parser.consume_value();
+            }
+            _ => {
+                expression_statement(parser);
+                parser.consume(Token::Conjunction, "expect '者'");
+            }
+        }
+        let then_jump = parser.emit_jump(opcode::JUMP_IF_FALSE);
+        parser.emit_u8(opcode::POP);
+        block_statement(parser, [Token::Else, Token::ElseIf]);
+
+        exit_jumps.push(parser.emit_jump(opcode::JUMP));
+        parser.patch_jump(then_jump);
+        parser.emit_u8(opcode::POP);
+
+        if !parser.check(Token::ElseIf) {
+            break;
+        }
+    }
 
     if parser.check(Token::Else) {
         parser.advance();
         block_statement(parser, []);
     }
 
-    parser.patch_jump(else_jump);
+    for exit_jump in exit_jumps {
+        parser.patch_jump(exit_jump);
+    }
 }
 
 pub fn boolean_algebra_statement<'a>(parser: &'a mut Parser) {
@@ -266,19 +419,41 @@ pub fn boolean_algebra_statement<'a>(parser: &'a mut Parser) {
 
 pub fn for_while_statement<'a>(parser: &'a mut Parser) {
     let break_jump = parser.emit_jump(opcode::RECORD_BREAK);
+    let continue_jump = parser.emit_jump(opcode::RECORD_CONTINUE);
     parser.advance();
     let loop_start = parser.current_code_len();
+    parser.patch_jump(continue_jump);
+    parser.begin_loop();
     block_statement(parser, []);
+    parser.end_loop();
     parser.emit_loop(loop_start);
     parser.patch_jump(break_jump);
     parser.emit_u8(opcode::DISCARD_BREAK);
+    parser.emit_u8(opcode::DISCARD_CONTINUE);
 }
 
 pub fn break_statement<'a>(parser: &'a mut Parser) {
     parser.advance();
+
+    if parser.current_compiler().loop_depth() == 0 {
+        parser.error("cannot use '乃止' outside of a loop.");
+        return;
+    }
+
     parser.emit_u8(opcode::BREAK)
 }
 
+pub fn continue_statement<'a>(parser: &'a mut Parser) {
+    parser.advance();
+
+    if parser.current_compiler().loop_depth() == 0 {
+        parser.error("cannot use '乃止是遍' outside of a loop.");
+        return;
+    }
+
+    parser.emit_u8(opcode::CONTINUE)
+}
+
 pub fn for_statement<'a>(parser: &'a mut Parser) {
     parser.advance();
     parser.expression();
@@ -287,11 +462,13 @@ pub fn for_statement<'a>(parser: &'a mut Parser) {
     let name = String::from("inner_for_loop_var");
     parser.begin_scope();
 
-    let break_jump = parser.emit_jump(opcode::RECORD_BREAK);
-
     // 吾有一數。曰「inner_for_loop_var」。名之曰「inner_for_loop_var」。
     parser.define_local_variable(name.as_str());
 
+    // record after the loop var defined, break will drop locals above it.
+    let break_jump = parser.emit_jump(opcode::RECORD_BREAK);
+    let continue_jump = parser.emit_jump(opcode::RECORD_CONTINUE);
+
     let slot = parser
         .resolve_local(name)
         .expect("should inject temp var into for loop.");
@@ -308,6 +485,8 @@ pub fn for_statement<'a>(parser: &'a mut Parser) {
 
     let body_jump = parser.emit_jump(opcode::JUMP);
     let increase_start = parser.current_code_len();
+    // continue still needs to count down.
+    parser.patch_jump(continue_jump);
 
     // 減「inner_for_loop_var」以一
     // 昔之「inner_for_loop_var」今其是矣
@@ -322,7 +501,9 @@ pub fn for_statement<'a>(parser: &'a mut Parser) {
     parser.emit_loop(loop_start);
     parser.patch_jump(body_jump);
 
+    parser.begin_loop();
     block_statement(parser, []);
+    parser.end_loop();
 
     parser.emit_loop(increase_start);
     parser.patch_jump(exit_jump);
@@ -330,6 +511,78 @@ pub fn for_statement<'a>(parser: &'a mut Parser) {
 
     parser.patch_jump(break_jump);
     parser.emit_u8(opcode::DISCARD_BREAK);
+    parser.emit_u8(opcode::DISCARD_CONTINUE);
+
+    parser.end_scope();
+}
+
+pub fn for_each_statement<'a>(parser: &'a mut Parser) {
+    parser.advance();
+
+    let target = String::from("inner_for_each_target");
+    let index = String::from("inner_for_each_index");
+    parser.begin_scope();
+
+    parser.expression();
+    parser.define_local_variable(target.as_str());
+    parser.emit_constant(Value::Number(0.0));
+    parser.define_local_variable(index.as_str());
+
+    let target = parser
+        .resolve_local(target)
+        .expect("should inject temp var into for each loop.");
+    let index = parser
+        .resolve_local(index)
+        .expect("should inject temp var into for each loop.");
+
+    parser.consume(Token::ForEachIn, "expect '中之' in for each statement.");
+    parser.consume(
+        Token::Identifier,
+        "expect a variable name in for each statement.",
+    );
+    let name = parser.get_prev_token_string();
+
+    let break_jump = parser.emit_jump(opcode::RECORD_BREAK);
+    let continue_jump = parser.emit_jump(opcode::RECORD_CONTINUE);
+    let loop_start = parser.current_code_len();
+    parser.patch_jump(continue_jump);
+
+    // 「inner_for_each_index」小於「inner_for_each_target」之長
+    parser.emit_bytes(opcode::GET_LOCAL, index);
+    parser.emit_bytes(opcode::GET_LOCAL, target);
+    parser.emit_u8(opcode::LENGTH);
+    parser.emit_u8(opcode::LESS);
+
+    let exit_jump = parser.emit_jump(opcode::JUMP_IF_FALSE);
+    parser.emit_u8(opcode::POP);
+
+    // 加「inner_for_each_index」以一
+    // 昔之「inner_for_each_index」今其是矣
+    parser.emit_bytes(opcode::GET_LOCAL, index);
+    parser.emit_constant(Value::Number(1.0));
+    parser.emit_u8(opcode::ADD);
+    parser.emit_u8(opcode::PREPOSITION_RIGHT);
+    parser.emit_bytes(opcode::SET_LOCAL, index);
+
+    // every round has its own 「name」, it's dropped at the end of the round.
+    parser.begin_scope();
+    parser.emit_bytes(opcode::GET_LOCAL, target);
+    parser.emit_bytes(opcode::GET_LOCAL, index);
+    parser.emit_u8(opcode::INDEX);
+    parser.define_local_variable(name.as_str());
+
+    parser.begin_loop();
+    block_statement(parser, []);
+    parser.end_loop();
+    parser.end_scope();
+
+    parser.emit_loop(loop_start);
+    parser.patch_jump(exit_jump);
+    parser.emit_u8(opcode::POP);
+
+    parser.patch_jump(break_jump);
+    parser.emit_u8(opcode::DISCARD_BREAK);
+    parser.emit_u8(opcode::DISCARD_CONTINUE);
 
     parser.end_scope();
 }
@@ -337,10 +590,17 @@ pub fn for_statement<'a>(parser: &'a mut Parser) {
 pub fn fun_statement<'a>(parser: &'a mut Parser) {
     parser.advance();
     parser.consume(Token::NameIs, "expect '名之曰' in function declaration.");
+    // inside a block the name is declared as a local before the body is compiled,
+    // so the body captures its own slot and can call itself.
     let global = parse_variable(parser, "expect function name.");
     function(parser, FunctionType::Function);
 
-    parser.define_global(global.unwrap());
+    if let Some(global) = global {
+        parser.define_global(global);
+    } else {
+        parser.emit_u8(opcode::DEFINE_LOCAL);
+        parser.emit_u8(0);
+    }
     parser.emit_u8(opcode::POP);
 }
 
@@ -351,16 +611,15 @@ pub fn function<'a>(parser: &'a mut Parser, kind: FunctionType) {
     if parser.is_match(Token::FunctionReady) {
         parser.consume(Token::FunctionArg, "expect '必先得'");
 
-        while !parser.check(Token::FunctionBodyBegin) {
-            if parser.is_match(Token::Is) {
-                parser.current_compiler_mut().function_mut().add_arity(1);
-                parse_variable(parser, "expect a parameter name.");
-                parser.emit_u8(opcode::DEFINE_LOCAL);
-                parser.emit_u8(0);
-                parser.emit_u8(opcode::POP);
-            } else {
-                parser.advance();
-            }
+        let arity = parameters(parser);
+
+        // the first argument is the deepest one on stack, it belongs to the first parameter.
+        for i in 0..arity {
+            parser.emit_u8(opcode::DEFINE_LOCAL);
+            parser.emit_u8((arity - 1 - i) as u8);
+        }
+        for _ in 0..arity {
+            parser.emit_u8(opcode::POP);
         }
     }
 
@@ -371,13 +630,81 @@ pub fn function<'a>(parser: &'a mut Parser, kind: FunctionType) {
     parser.consume(Token::Identifier, "expect identifier in function end.");
     parser.consume(Token::FunctionEnd2, "expect '之術也' in function end.");
 
-    if let Some(function) = parser.end_compiler() {
-        let closure_id = parser.add_closure(function);
+    let up_values = parser.current_compiler().up_values().clone();
+    if let Some(mut function) = parser.end_compiler() {
+        function.set_up_value_count(up_values.len());
+        let fun_id = parser.add_function(function);
         let id = parser
-            .make_constant(Value::Closure(closure_id))
+            .make_constant(Value::Function(fun_id))
             .expect("should be able to make constant");
 
-        parser.emit_bytes(opcode::CONSTANT, id);
+        // every execution of 吾有一術 makes a new closure with its own captured variables.
+        parser.emit_bytes(opcode::CLOSURE, id);
+        for up_value in up_values {
+            parser.emit_u8(up_value.is_local as u8);
+            parser.emit_u32(up_value.index);
+        }
+    }
+}
+
+// 必先得二數曰「甲」曰「乙」一言曰「丙」, each group tells how many parameters of a type follow.
+fn parameters(parser: &mut Parser) -> usize {
+    let mut arity = 0;
+
+    while !parser.check(Token::FunctionBodyBegin) && !parser.check(Token::Eof) {
+        if !parser.is_match(Token::Number) {
+            parser.error_at_current("expect the number of parameters.");
+            skip_parameters(parser);
+            return arity;
+        }
+        let number = parser.get_prev_token_string();
+        let count = hanzi2num(&number).and_then(|s| s.parse::<usize>().ok());
+
+        if !parser.is_match(Token::Type) {
+            parser.error_at_current("expect the type of parameters.");
+            skip_parameters(parser);
+            return arity;
+        }
+        let kind = Kind::from_name(&parser.get_prev_token_string()).unwrap_or(Kind::Any);
+
+        let mut names = 0;
+        while parser.is_match(Token::Is) {
+            if arity == MAX_PARAMETERS {
+                parser.error(&format!(
+                    "a 術 can't have more than {} parameters.",
+                    MAX_PARAMETERS
+                ));
+                skip_parameters(parser);
+                return arity;
+            }
+            names += 1;
+            arity += 1;
+            parse_variable(parser, "expect a parameter name.");
+            parser.declare_kind(Some(kind));
+
+            let function = parser.current_compiler_mut().function_mut();
+            function.add_arity(1);
+            function.add_param_kind(kind);
+        }
+
+        if count != Some(names) {
+            parser.error(&format!(
+                "expect {} names after {}{} but got {}.",
+                count.map_or(String::from("?"), |count| count.to_string()),
+                number,
+                kind.name(),
+                names
+            ));
+        }
+    }
+
+    arity
+}
+
+// still compile the body after a broken parameter list.
+fn skip_parameters(parser: &mut Parser) {
+    while !parser.check(Token::FunctionBodyBegin) && !parser.check(Token::Eof) {
+        parser.advance();
     }
 }
 
@@ -386,20 +713,23 @@ pub fn call_statement<'a>(parser: &'a mut Parser) {
     parser.expression();
     let mut arg_count = 0;
     if parser.is_match(Token::PrepositionLeft) {
-        arg_count = argument_list(parser);
-    } else if parser.is_match(Token::PrepositionLeft) {
-        parser.error_at_current("only support '於'' in function call now.")
+        // 施「f」於「甲」於「乙」 is f(甲, 乙)
+        arg_count = argument_list(parser, Token::PrepositionLeft);
+    } else if parser.is_match(Token::PrepositionRight) {
+        // 施「f」以「甲」以「乙」 is f(乙, 甲), the nearest argument comes last.
+        arg_count = argument_list(parser, Token::PrepositionRight);
+        parser.emit_bytes(opcode::REVERSE, arg_count);
     }
 
     parser.emit_bytes(opcode::CALL, arg_count);
 }
 
-pub fn argument_list<'a>(parser: &'a mut Parser) -> u32 {
+pub fn argument_list<'a>(parser: &'a mut Parser, separator: Token) -> u32 {
     let mut arg_count: u32 = 0;
     while parser.is_match_literal() {
         parser.expression();
         arg_count += 1;
-        if !parser.is_match(Token::PrepositionLeft) {
+        if !parser.is_match(separator.clone()) {
             break;
         }
     }
@@ -407,13 +737,204 @@ pub fn argument_list<'a>(parser: &'a mut Parser) -> u32 {
     arg_count
 }
 
-pub fn return_statement(parser: &mut Parser) {
-    if parser.current_compiler().fun_kind() == FunctionType::Script {
-        parser.error_at_current("cannot return from top-level code.");
+// 以「甲」以「乙」施「f」 is f(甲, 乙)
+pub fn prefix_call_statement(parser: &mut Parser) {
+    parser.advance();
+    let mut arg_count: u32 = 0;
+    while parser.is_match_literal() {
+        parser.expression();
+        arg_count += 1;
+        parser.is_match(Token::PrepositionRight);
+    }
+
+    if arg_count == 0 {
+        parser.error_at_current("expect arguments after '以'.");
         return;
     }
 
+    parser.consume(Token::Call, "expect '施' after arguments.");
+    parser.expression();
+    parser.emit_bytes(opcode::CALL_STACKED, arg_count);
+}
+
+// 取二以施「f」 calls f with the last two values on stack.
+pub fn take_call_statement(parser: &mut Parser) {
+    parser.advance();
+    parser.consume(Token::Number, "expect a number after '取'.");
+    let count = parser.get_prev_token_string();
+    let count = match hanzi2num(&count).and_then(|s| s.parse::<u32>().ok()) {
+        Some(count) => count,
+        None => {
+            parser.error("expect a positive integer after '取'.");
+            return;
+        }
+    };
+
+    parser.consume(Token::PrepositionRight, "expect '以施' after '取'.");
+    parser.consume(Token::Call, "expect '以施' after '取'.");
+    parser.expression();
+    parser.emit_bytes(opcode::CALL_STACKED, count);
+}
+
+pub fn push_statement(parser: &mut Parser) {
     parser.advance();
     parser.expression();
+
+    if !parser.check(Token::PrepositionRight) {
+        parser.error_at_current("expect '以' in push statement.");
+        return;
+    }
+
+    while parser.is_match(Token::PrepositionRight) {
+        parser.expression();
+        parser.emit_u8(opcode::APPEND);
+        parser.pop_operands(1);
+    }
+
+    // the list has been changed in place, we don't need it in stack anymore.
+    parser.emit_u8(opcode::POP);
+}
+
+pub fn try_statement(parser: &mut Parser) {
+    parser.advance();
+
+    let handler_jump = parser.emit_jump(opcode::TRY);
+    block_statement(parser, [Token::Catch, Token::TryEnd]);
+    parser.emit_u8(opcode::END_TRY);
+    let end_jump = parser.emit_jump(opcode::JUMP);
+
+    // the vm unwinds to here with the error on top of stack.
+    parser.patch_jump(handler_jump);
+    let error = String::from("inner_try_error");
+    parser.begin_scope();
+    parser.define_local_variable(error.as_str());
+    let error = parser
+        .resolve_local(error)
+        .expect("should inject temp var into try statement.");
+
+    if parser.is_match(Token::Catch) {
+        let mut exit_jumps = vec![];
+        let mut has_error_clause = false;
+
+        // 豈「「名」」之禍歟
+        while parser.is_match(Token::CatchError) {
+            has_error_clause = true;
+            parser.emit_bytes(opcode::GET_LOCAL, error);
+            parser.expression();
+            parser.consume(Token::CatchEnd, "expect '之禍歟' in catch clause.");
+            parser.emit_u8(opcode::EQUAL_EQUAL);
+
+            let next_jump = parser.emit_jump(opcode::JUMP_IF_FALSE);
+            parser.emit_u8(opcode::POP);
+            block_statement(parser, [Token::CatchError, Token::CatchAll, Token::TryEnd]);
+            exit_jumps.push(parser.emit_jump(opcode::JUMP));
+            parser.patch_jump(next_jump);
+            parser.emit_u8(opcode::POP);
+        }
+
+        // 不知何禍歟 catches everything, 名之曰「e」 is optional.
+        if parser.is_match(Token::CatchAll) {
+            parser.begin_scope();
+            if parser.is_match(Token::NameIs) {
+                parser.consume(Token::Identifier, "expect a variable name for the error.");
+                let name = parser.get_prev_token_string();
+                parser.emit_bytes(opcode::GET_LOCAL, error);
+                parser.define_local_variable(name.as_str());
+            }
+            block_statement(parser, [Token::TryEnd]);
+            parser.end_scope();
+        } else if has_error_clause {
+            // no clause matched, pass the error to the outer 姑妄行此.
+            parser.emit_bytes(opcode::GET_LOCAL, error);
+            parser.emit_u8(opcode::THROW);
+        }
+
+        for exit_jump in exit_jumps {
+            parser.patch_jump(exit_jump);
+        }
+    } else {
+        parser.error_at_current("expect '如事不諧' in try statement.");
+    }
+
+    parser.consume(Token::TryEnd, "expect '乃作罷' in try statement.");
+    parser.end_scope();
+    parser.patch_jump(end_jump);
+}
+
+pub fn throw_statement(parser: &mut Parser) {
+    parser.advance();
+    parser.expression();
+    parser.consume(Token::ThrowEnd, "expect '之禍' in throw statement.");
+    parser.emit_u8(opcode::THROW);
+}
+
+pub fn import_statement(parser: &mut Parser) {
+    parser.advance();
+    parser.consume(Token::String, "expect a book name after '吾嘗觀'.");
+    let name = parser.get_prev_token_string();
+    parser.consume(Token::ImportEnd, "expect '之書' in import statement.");
+
+    let module = match parser.load_module(&name) {
+        Some(module) => module,
+        None => {
+            // the book is already reported, its names are not worth another error.
+            if parser.is_match(Token::ImportNames) {
+                while parser.is_match(Token::Identifier) {}
+                parser.is_match(Token::ImportNamesEnd);
+            }
+            return;
+        }
+    };
+
+    // run the book the first time it's seen, it leaves nothing useful on stack.
+    parser.emit_bytes(opcode::IMPORT, module);
+    parser.emit_u8(opcode::POP);
+
+    if !parser.is_match(Token::ImportNames) {
+        return;
+    }
+
+    // 方悟「甲」「乙」之義
+    while parser.check(Token::Identifier) {
+        let variable = parser.pick_str(parser.current()).to_owned();
+        if !parser.module_exports(module).contains(&variable) {
+            parser.error_at_current(format!("book {} has no public {}.", name, variable).as_str());
+            return;
+        }
+
+        let global = parse_variable(parser, "expect a name to import.");
+        let constant = parser.identifier_constant().unwrap();
+        parser.emit_bytes(opcode::IMPORT_NAME, module);
+        parser.emit_u32(constant);
+
+        if let Some(global) = global {
+            parser.define_global(global);
+        } else {
+            parser.emit_u8(opcode::DEFINE_LOCAL);
+            parser.emit_u8(0);
+        }
+        parser.emit_u8(opcode::POP);
+    }
+
+    parser.consume(Token::ImportNamesEnd, "expect '之義' in import statement.");
+}
+
+pub fn return_statement(parser: &mut Parser) {
+    parser.advance();
+
+    // step over the keyword first, so the parser can recover at the next return.
+    if parser.current_compiler().fun_kind() == FunctionType::Script {
+        parser.error("cannot return from top-level code.");
+        return;
+    }
+
+    match parser.previous().get_value() {
+        Token::ReturnNil => parser.emit_u8(opcode::NIL),
+        // 乃得矣 gives back what 其 refers to.
+        Token::ReturnPrev => parser.prev_value(),
+        _ => parser.expression(),
+    }
+
+    // RETURN drops the locals of every block it's nested in.
     parser.emit_u8(opcode::RETURN);
 }
@@ -4,11 +4,15 @@ fn get_keywords() -> Vec<(Vec<char>, Token)> {
     let mut key_defines = Vec::new();
 
     key_defines.push(("吾有", Token::Decl));
-    key_defines.push(("今有", Token::Decl));
+    key_defines.push(("今有", Token::PublicDecl));
     key_defines.push(("有", Token::DeclShort));
     key_defines.push(("數", Token::Type));
     key_defines.push(("言", Token::Type));
     key_defines.push(("爻", Token::Type));
+    key_defines.push(("列", Token::Type));
+    key_defines.push(("物", Token::Type));
+    key_defines.push(("術", Token::Type));
+    key_defines.push(("元", Token::Type));
     key_defines.push(("書之", Token::Print));
     key_defines.push(("名之曰", Token::NameIs));
     key_defines.push(("曰", Token::Is));
@@ -19,6 +23,8 @@ fn get_keywords() -> Vec<(Vec<char>, Token)> {
     key_defines.push(("加", Token::Plus));
     key_defines.push(("減", Token::Minus));
     key_defines.push(("乘", Token::Star));
+    key_defines.push(("除", Token::Slash));
+    key_defines.push(("所餘幾何", Token::Remainder));
     key_defines.push(("於", Token::PrepositionLeft));
     key_defines.push(("以", Token::PrepositionRight));
 
@@ -31,12 +37,17 @@ fn get_keywords() -> Vec<(Vec<char>, Token)> {
 
     key_defines.push(("昔之", Token::AssignFrom));
     key_defines.push(("今", Token::AssignTo));
+    key_defines.push(("今不復存矣", Token::Delete));
     key_defines.push(("者", Token::Conjunction));
     key_defines.push(("其", Token::Prev));
+    key_defines.push(("噫", Token::Clear));
     key_defines.push(("是矣", Token::Sure));
 
     key_defines.push(("若", Token::If));
     key_defines.push(("若非", Token::Else));
+    key_defines.push(("或若", Token::ElseIf));
+    key_defines.push(("若其然者", Token::IfTrue));
+    key_defines.push(("若其不然者", Token::IfFalse));
     key_defines.push(("云云", Token::YunYun));
     key_defines.push(("也", Token::Ye));
 
@@ -50,8 +61,12 @@ fn get_keywords() -> Vec<(Vec<char>, Token)> {
     key_defines.push(("為是", Token::For));
     key_defines.push(("遍", Token::ForMid));
     key_defines.push(("乃止", Token::Break));
+    key_defines.push(("乃止是遍", Token::Continue));
+    key_defines.push(("凡", Token::ForEach));
+    key_defines.push(("中之", Token::ForEachIn));
 
     key_defines.push(("吾有一術", Token::Fun));
+    key_defines.push(("今有一術", Token::PublicFun));
     key_defines.push(("欲行是術", Token::FunctionReady));
     key_defines.push(("必先得", Token::FunctionArg));
     key_defines.push(("是術曰", Token::FunctionBodyBegin));
@@ -59,7 +74,37 @@ fn get_keywords() -> Vec<(Vec<char>, Token)> {
     key_defines.push(("是謂", Token::FunctionEnd1));
     key_defines.push(("之術也", Token::FunctionEnd2));
     key_defines.push(("施", Token::Call));
+    key_defines.push(("取", Token::Take));
     key_defines.push(("乃得", Token::Return));
+    key_defines.push(("乃得矣", Token::ReturnPrev));
+    key_defines.push(("乃歸空無", Token::ReturnNil));
+
+    key_defines.push(("充", Token::Push));
+    key_defines.push(("之", Token::Of));
+    key_defines.push(("之長", Token::Length));
+    key_defines.push(("之其餘", Token::Rest));
+
+    key_defines.push(("其物如是", Token::ObjectBegin));
+    key_defines.push(("物之", Token::ObjectProperty));
+    key_defines.push(("之物也", Token::ObjectEnd));
+
+    key_defines.push(("姑妄行此", Token::Try));
+    key_defines.push(("如事不諧", Token::Catch));
+    key_defines.push(("豈", Token::CatchError));
+    key_defines.push(("之禍歟", Token::CatchEnd));
+    key_defines.push(("不知何禍歟", Token::CatchAll));
+    key_defines.push(("乃作罷", Token::TryEnd));
+    key_defines.push(("嗚呼", Token::Throw));
+    key_defines.push(("之禍", Token::ThrowEnd));
+
+    key_defines.push(("注曰", Token::Comment));
+    key_defines.push(("疏曰", Token::Comment));
+    key_defines.push(("批曰", Token::Comment));
+
+    key_defines.push(("吾嘗觀", Token::Import));
+    key_defines.push(("之書", Token::ImportEnd));
+    key_defines.push(("方悟", Token::ImportNames));
+    key_defines.push(("之義", Token::ImportNamesEnd));
 
     let keywords: Vec<(Vec<char>, Token)> = key_defines
         .iter()
@@ -11,6 +11,9 @@ pub struct Scanner {
     current_pos: BytePos,
     start_pos: BytePos,
     line: usize,
+    start_line: usize,
+    // lines of the macro expanded text back to the user's file.
+    line_map: Vec<usize>,
     sorted_keywords: Vec<(Vec<char>, Token)>,
 }
 
@@ -23,9 +26,20 @@ impl Scanner {
             current_pos: BytePos::default(),
             start_pos: BytePos::default(),
             line: 1,
+            start_line: 1,
+            line_map: vec![],
             sorted_keywords: get_sorted_keywords(),
         }
     }
+    pub fn set_line_map(&mut self, line_map: Vec<usize>) {
+        self.line_map = line_map;
+    }
+    fn source_line(&self) -> usize {
+        self.line_map
+            .get(self.start_line - 1)
+            .copied()
+            .unwrap_or(self.start_line)
+    }
 
     fn scan_tokens(&mut self) -> Vec<WithSpan<Token>> {
         let mut list = vec![];
@@ -50,6 +64,7 @@ impl Scanner {
         self.skip_whitespace();
         self.start = self.current;
         self.start_pos = self.current_pos;
+        self.start_line = self.line;
 
         if self.is_at_end() {
             return self.make_token(Token::Eof);
@@ -91,6 +106,9 @@ impl Scanner {
                 }
 
                 self.step_by(data.0.unwrap());
+                if data.1 == Some(Token::Comment) {
+                    return self.comment();
+                }
                 return self.make_token(data.1.unwrap());
             }
         }
@@ -102,10 +120,7 @@ impl Scanner {
                 Some(' ') | Some('\r') | Some('\t') | Some('。') | Some('、') | Some('也') => {
                     self.advance();
                 }
-                Some('\n') => {
-                    self.line += 1;
-                    self.advance();
-                }
+                Some('\n') => self.advance(),
                 _ => return,
             }
         }
@@ -130,14 +145,14 @@ impl Scanner {
             end_pos.backwards(ch);
         }
 
-        WithSpan::new(token, Span::from(start_pos, end_pos), self.line)
+        WithSpan::new(token, Span::from(start_pos, end_pos), self.source_line())
     }
 
     pub fn make_token(&self, token: Token) -> WithSpan<Token> {
         WithSpan::new(
             token,
             Span::from(self.start_pos, self.current_pos),
-            self.line,
+            self.source_line(),
         )
     }
 
@@ -145,7 +160,11 @@ impl Scanner {
         let next_c = self.chars.get(self.current);
 
         if let Some(c) = next_c {
-            self.current_pos.shift(*c)
+            self.current_pos.shift(*c);
+            // strings and comments can span lines too.
+            if *c == '\n' {
+                self.line += 1;
+            }
         }
         self.current += 1
     }
@@ -242,6 +261,40 @@ impl Scanner {
         return self.error_token("unterminated string.");
     }
 
+    // 注曰「「…」」 is one token from the keyword to the end of its string.
+    fn comment(&mut self) -> WithSpan<Token> {
+        self.skip_whitespace();
+
+        let closed = match (self.peek(), self.peek_next()) {
+            (Some('「'), Some('「')) => {
+                self.step_by(2);
+                loop {
+                    self.consume_while(|ch| ch != Some('」'));
+                    if self.is_at_end() {
+                        break false;
+                    }
+                    if self.peek_next() == Some('」') {
+                        self.step_by(2);
+                        break true;
+                    }
+                    self.advance();
+                }
+            }
+            (Some('『'), _) => {
+                self.advance();
+                self.consume_while(|ch| ch != Some('』'));
+                self.consume('』')
+            }
+            _ => return self.error_token("expect a string after comment."),
+        };
+
+        if !closed {
+            return self.error_token("unterminated comment.");
+        }
+
+        self.make_token(Token::Comment)
+    }
+
     fn identifier(&mut self) -> WithSpan<Token> {
         self.consume_while(|ch| ch != Some('」'));
 
@@ -362,4 +415,14 @@ mod test {
     fn test_define() {
         generate_tokens_snapshot("有數一名之曰「丙」")
     }
+
+    #[test]
+    fn test_comment() {
+        generate_tokens_snapshot("注曰「「以「甲」為數」」疏曰『註解』批曰「「未完」」書之")
+    }
+
+    #[test]
+    fn test_unterminated_comment() {
+        generate_tokens_snapshot("注曰「「未完」")
+    }
 }
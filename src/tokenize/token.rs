@@ -3,6 +3,7 @@ use serde::Serialize;
 #[derive(Clone, Debug, Serialize, PartialEq)]
 pub enum Token {
     Decl,
+    PublicDecl, // 今有
     DeclShort,
     Type,
 
@@ -17,9 +18,11 @@ pub enum Token {
 
     Print, // 書之
 
-    Plus,  // 加
-    Minus, // 減
-    Star,  // 乘
+    Plus,      // 加
+    Minus,     // 減
+    Star,      // 乘
+    Slash,     // 除
+    Remainder, // 所餘幾何
 
     PrepositionLeft,  // 於
     PrepositionRight, // 以
@@ -33,7 +36,9 @@ pub enum Token {
 
     AssignFrom, // '昔之'
     AssignTo,   // 今
+    Delete,     // 今不復存矣
     Prev,       // 其
+    Clear,      // 噫
     Fu,         // 夫
     Invert,     // 變
 
@@ -42,25 +47,62 @@ pub enum Token {
     YunYun,      // 云云
     Ye,          // 也
 
-    If,   // 若
-    Else, // 若非
+    If,      // 若
+    Else,    // 若非
+    ElseIf,  // 或若
+    IfTrue,  // 若其然者
+    IfFalse, // 若其不然者
 
-    Loop,   // 恆為是
-    For,    // 為是
-    ForMid, // 遍
-    Break,  // 乃止
+    Loop,     // 恆為是
+    For,      // 為是
+    ForMid,   // 遍
+    Break,    // 乃止
+    Continue, // 乃止是遍
+
+    ForEach,   // 凡
+    ForEachIn, // 中之
 
     And, // 中無陰乎,
     Or,  // 中有陽乎
 
     Fun,               // 吾有一術
+    PublicFun,         // 今有一術
     FunctionReady,     // 欲行是術
     FunctionArg,       // 必先得
     FunctionBodyBegin, // 是術曰
     FunctionEnd1,      // 是謂
     FunctionEnd2,      // 之術也
     Call,              // 施
+    Take,              // 取
     Return,            // 乃得
+    ReturnPrev,        // 乃得矣
+    ReturnNil,         // 乃歸空無
+
+    Push,   // 充
+    Of,     // 之
+    Length, // 之長
+    Rest,   // 之其餘
+
+    ObjectBegin,    // 其物如是
+    ObjectProperty, // 物之
+    ObjectEnd,      // 之物也
+
+    Try,        // 姑妄行此
+    Catch,      // 如事不諧
+    CatchError, // 豈
+    CatchEnd,   // 之禍歟
+    CatchAll,   // 不知何禍歟
+    TryEnd,     // 乃作罷
+    Throw,      // 嗚呼
+    ThrowEnd,   // 之禍
+
+    Import,         // 吾嘗觀
+    ImportEnd,      // 之書
+    ImportNames,    // 方悟
+    ImportNamesEnd, // 之義
+
+    // 注曰, 疏曰 and 批曰 with their string, the compiler skips it.
+    Comment,
 
     Eof,
     Error(String),
@@ -1,6 +1,6 @@
 use crate::{
     interner::StrId,
-    object::{ClosureId, FunId},
+    object::{ClosureId, FunId, ListId, NativeId, ObjectId},
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -11,6 +11,69 @@ pub enum Value {
     String(StrId),
     Function(FunId),
     Closure(ClosureId),
+    Native(NativeId),
+    List(ListId),
+    Object(ObjectId),
+}
+
+// the types written in 吾有一數 or 必先得一言, 元 accepts anything.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Number,
+    String,
+    Bool,
+    List,
+    Object,
+    Function,
+    Any,
+}
+
+const KINDS: [Kind; 7] = [
+    Kind::Number,
+    Kind::String,
+    Kind::Bool,
+    Kind::List,
+    Kind::Object,
+    Kind::Function,
+    Kind::Any,
+];
+
+impl Kind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        KINDS.iter().find(|kind| kind.name() == name).copied()
+    }
+    pub fn from_u8(byte: u8) -> Option<Self> {
+        KINDS.get(byte as usize).copied()
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Number => "數",
+            Kind::String => "言",
+            Kind::Bool => "爻",
+            Kind::List => "列",
+            Kind::Object => "物",
+            Kind::Function => "術",
+            Kind::Any => "元",
+        }
+    }
+    // nil has no type, only 元 accepts it.
+    pub fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Nil => None,
+            Value::Number(_) => Some(Kind::Number),
+            Value::String(_) => Some(Kind::String),
+            Value::Bool(_) => Some(Kind::Bool),
+            Value::List(_) => Some(Kind::List),
+            Value::Object(_) => Some(Kind::Object),
+            Value::Function(_) | Value::Closure(_) | Value::Native(_) => Some(Kind::Function),
+        }
+    }
+    pub fn name_of(value: &Value) -> &'static str {
+        Kind::of(value).map(|kind| kind.name()).unwrap_or("無")
+    }
+    pub fn accepts(&self, value: &Value) -> bool {
+        *self == Kind::Any || Kind::of(value) == Some(*self)
+    }
 }
 
 pub fn value_equal(a: Value, b: Value) -> bool {
@@ -18,6 +81,8 @@ pub fn value_equal(a: Value, b: Value) -> bool {
         (Value::Number(a), Value::Number(b)) => a == b,
         (Value::Bool(a), Value::Bool(b)) => a == b,
         (Value::String(a), Value::String(b)) => a == b,
+        (Value::List(a), Value::List(b)) => a == b,
+        (Value::Object(a), Value::Object(b)) => a == b,
         _ => false,
     }
 }
@@ -38,7 +103,7 @@ pub fn is_falsy(value: &Value) -> bool {
 
 pub fn is_function_or_closure(value: &Value) -> bool {
     match value {
-        Value::Function(_) | Value::Closure(_) => true,
+        Value::Function(_) | Value::Closure(_) | Value::Native(_) => true,
         _ => false,
     }
 }
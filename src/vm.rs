@@ -1,13 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{hash_map::Entry, HashMap};
 
 use crate::{
     chunk::Chunk,
     interner::StrId,
-    interpreter::{CallFrame, InterpretStatus, Runtime},
+    interpreter::{CallFrame, Handler, InterpretStatus, Runtime},
     memory::free_object,
-    object::{ClosureId},
+    module::ModuleId,
+    object::{Captured, ClosureId, NativeError, NativeId, Object, UpValueId},
     opcode,
-    value::{is_falsy, is_function_or_closure, is_less, value_equal, Value},
+    value::{is_falsy, is_function_or_closure, is_less, value_equal, Kind, Value},
 };
 
 #[derive(Clone, Copy, PartialEq)]
@@ -20,8 +21,14 @@ pub struct VM<'a> {
     stack: Vec<Value>,
     local_stack: Vec<Value>,
     runtime: &'a mut Runtime,
-    globals: HashMap<String, Value>,
-    break_points: Vec<*const u8>,
+    // every module has its own globals, keyed by the module running them.
+    globals: HashMap<ModuleId, HashMap<String, Value>>,
+    // the error thrown by the last instruction, waiting to be caught.
+    error: Option<Value>,
+    // captured variables still living in the local stack.
+    open_up_values: Vec<UpValueId>,
+    // local slots deleted by 今不復存矣, with their names for the error.
+    deleted_locals: HashMap<usize, StrId>,
 }
 
 impl<'a> VM<'a> {
@@ -31,9 +38,14 @@ impl<'a> VM<'a> {
             local_stack: vec![],
             runtime,
             globals: HashMap::new(),
-            break_points: vec![],
+            error: None,
+            open_up_values: vec![],
+            deleted_locals: HashMap::new(),
         }
     }
+    pub fn runtime_mut(&mut self) -> &mut Runtime {
+        self.runtime
+    }
     pub fn frame_mut(&mut self) -> &mut CallFrame {
         self.runtime.current_frame_mut()
     }
@@ -78,7 +90,10 @@ impl<'a> VM<'a> {
             print!("[]")
         }
         for val in self.stack.iter() {
-            print!("[{:?}]", val);
+            match val {
+                Value::Native(_) => print!("[{}]", self.format_value(val)),
+                _ => print!("[{:?}]", val),
+            }
         }
 
         println!("  ");
@@ -99,6 +114,12 @@ impl<'a> VM<'a> {
         }
 
         loop {
+            if let Some(error) = self.error.take() {
+                if !self.catch(error) {
+                    return InterpretStatus::RuntimeError;
+                }
+            }
+
             if mode == VMMode::Debug {
                 self.show_stack();
                 self.disassemble_instruction();
@@ -127,9 +148,7 @@ impl<'a> VM<'a> {
                         return InterpretStatus::Ok;
                     }
 
-                    while self.local_stack.len() > local_len {
-                        self.local_stack.pop();
-                    }
+                    self.truncate_locals(local_len);
                     while self.stack.len() > stack_len {
                         self.stack.pop();
                     }
@@ -167,9 +186,13 @@ impl<'a> VM<'a> {
                 opcode::POP => {
                     self.stack.pop();
                 }
-                opcode::ADD => self.binary_op("+"),
-                opcode::SUBTRACT => self.binary_op("-"),
-                opcode::MULTIPLY => self.binary_op("*"),
+                opcode::ADD
+                | opcode::SUBTRACT
+                | opcode::MULTIPLY
+                | opcode::DIVIDE
+                | opcode::REMAINDER => {
+                    self.binary_op(byte);
+                }
                 opcode::INVERT => {
                     let val = match self.stack.pop() {
                         Some(Value::Bool(false)) => Some(true),
@@ -217,29 +240,36 @@ impl<'a> VM<'a> {
                     let str = self.read_string();
                     let offset = self.read_byte() as usize;
                     let value = self.peek(offset);
-                    if let (Some(value), Some(str)) = (value, str) {
-                        self.globals.insert(str, value.clone());
+                    if let (Some(value), Some(str)) = (value.cloned(), str) {
+                        self.module_globals().insert(str, value);
                     }
                 }
                 opcode::GET_GLOBAL => {
                     let str_id = self.read_str().expect("a valid str id");
+                    let module = self.module();
                     let str = self.runtime.interner().lookup(str_id);
-                    if let Some(value) = self.globals.get(str) {
+                    if let Some(value) = self.globals.entry(module).or_default().get(str) {
                         self.stack.push(value.clone());
+                    } else if let Some(value) = self.runtime.native_global(str) {
+                        self.stack.push(value);
                     } else {
                         self.runtime_error(format!("undefined variable {}", str).as_str());
-                        return InterpretStatus::RuntimeError;
+                        continue;
                     }
                 }
                 opcode::SET_GLOBAL => {
                     let str_id = self.read_str().expect("a valid str id");
+                    let module = self.module();
                     let str = self.runtime.interner().lookup(str_id);
                     let value = self.stack.pop();
                     if let Some(value) = value {
-                        self.globals.insert(str.to_owned(), value);
+                        self.globals
+                            .entry(module)
+                            .or_default()
+                            .insert(str.to_owned(), value);
                     } else {
                         self.runtime_error(format!("undefined variable {}", str).as_str());
-                        return InterpretStatus::RuntimeError;
+                        continue;
                     }
                 }
                 opcode::DEFINE_LOCAL => {
@@ -251,7 +281,11 @@ impl<'a> VM<'a> {
                 }
                 opcode::GET_LOCAL => {
                     let slot = self.read_u32() as usize;
-                    let value = self.local_stack.get(self.normalize_local_slot(slot));
+                    let slot = self.normalize_local_slot(slot);
+                    if !self.check_deleted(slot) {
+                        continue;
+                    }
+                    let value = self.local_stack.get(slot);
                     if let Some(value) = value {
                         self.stack.push(value.clone());
                     }
@@ -259,6 +293,7 @@ impl<'a> VM<'a> {
                 opcode::SET_LOCAL => {
                     let mut slot = self.read_u32() as usize;
                     slot = self.normalize_local_slot(slot);
+                    self.deleted_locals.remove(&slot);
 
                     self.local_stack.get_mut(slot).map(|x| {
                         let value = self.stack.pop();
@@ -267,8 +302,90 @@ impl<'a> VM<'a> {
                         }
                     });
                 }
+                opcode::DELETE_LOCAL => {
+                    let slot = self.read_u32() as usize;
+                    let slot = self.normalize_local_slot(slot);
+                    let name = match self.read_constant() {
+                        Some(Value::String(name)) => *name,
+                        _ => unreachable!("the name of a local should be a string."),
+                    };
+                    self.local_stack[slot] = Value::Nil;
+                    self.deleted_locals.insert(slot, name);
+                }
+                opcode::DELETE_GLOBAL => {
+                    let str_id = self.read_str().expect("a valid str id");
+                    let module = self.module();
+                    let str = self.runtime.interner().lookup(str_id);
+                    if self
+                        .globals
+                        .entry(module)
+                        .or_default()
+                        .remove(str)
+                        .is_none()
+                    {
+                        self.runtime_error(format!("undefined variable {}", str).as_str());
+                        continue;
+                    }
+                }
+                opcode::DELETE_INDEX => {
+                    self.delete_index();
+                }
                 opcode::POP_LOCAL => {
-                    self.local_stack.pop();
+                    self.truncate_locals(self.local_stack.len().saturating_sub(1));
+                }
+                opcode::GET_UPVALUE => {
+                    let index = self.read_u32() as usize;
+                    let id = self.frame_up_value(index);
+                    let value = match *self.runtime.get_up_value(&id) {
+                        Captured::Open(slot) => {
+                            if !self.check_deleted(slot) {
+                                continue;
+                            }
+                            self.local_stack[slot]
+                        }
+                        Captured::Closed(value) => value,
+                        Captured::Deleted(name) => {
+                            let name = self.runtime.interner().lookup(name).to_owned();
+                            self.runtime_error(format!("undefined variable {}", name).as_str());
+                            continue;
+                        }
+                    };
+                    self.stack.push(value);
+                }
+                opcode::SET_UPVALUE => {
+                    let index = self.read_u32() as usize;
+                    let id = self.frame_up_value(index);
+                    let value = self.stack.pop().unwrap();
+                    match *self.runtime.get_up_value(&id) {
+                        Captured::Open(slot) => {
+                            self.deleted_locals.remove(&slot);
+                            self.local_stack[slot] = value
+                        }
+                        Captured::Closed(_) | Captured::Deleted(_) => {
+                            *self.runtime.get_up_value_mut(&id) = Captured::Closed(value)
+                        }
+                    }
+                }
+                opcode::CLOSURE => {
+                    let fun_id = match self.read_constant() {
+                        Some(Value::Function(id)) => *id,
+                        _ => panic!("unreachable"),
+                    };
+                    let count = self.runtime.get_function(&fun_id).up_value_count();
+                    let mut up_values = vec![];
+                    for _ in 0..count {
+                        let is_local = self.read_byte() == 1;
+                        let index = self.read_u32() as usize;
+                        let id = if is_local {
+                            self.capture_up_value(self.normalize_local_slot(index))
+                        } else {
+                            self.frame_up_value(index)
+                        };
+                        up_values.push(id);
+                    }
+
+                    let id = self.runtime.add_closure(fun_id, up_values);
+                    self.stack.push(Value::Closure(id));
                 }
                 opcode::JUMP_IF_FALSE => {
                     let offset = self.read_u32();
@@ -316,44 +433,335 @@ impl<'a> VM<'a> {
                     self.skip(offset, false);
                 }
                 opcode::BREAK => {
-                    if let Some(ip) = self.break_points.last() {
-                        self.set_ip(ip.clone());
+                    if let Some((ip, local_len, handler_len)) =
+                        self.frame_mut().break_points_mut().last().cloned()
+                    {
+                        self.truncate_locals(local_len);
+                        self.frame_mut().handlers_mut().truncate(handler_len);
+                        self.set_ip(ip);
                     } else {
                         self.runtime_error("no loop to break.");
-                        return InterpretStatus::RuntimeError;
+                        continue;
                     }
                 }
                 opcode::DISCARD_BREAK => {
-                    self.break_points.pop();
+                    self.frame_mut().break_points_mut().pop();
                 }
                 opcode::RECORD_BREAK => {
                     let offset = self.read_u32();
                     let ip = unsafe { self.ip().add(offset as usize) };
-                    self.break_points.push(ip);
+                    let local_len = self.local_stack.len();
+                    let handler_len = self.frame().handlers().len();
+                    self.frame_mut()
+                        .break_points_mut()
+                        .push((ip, local_len, handler_len));
+                }
+                opcode::CONTINUE => {
+                    if let Some((ip, local_len, handler_len)) =
+                        self.frame_mut().continue_points_mut().last().cloned()
+                    {
+                        self.truncate_locals(local_len);
+                        self.frame_mut().handlers_mut().truncate(handler_len);
+                        self.set_ip(ip);
+                    } else {
+                        self.runtime_error("no loop to continue.");
+                        continue;
+                    }
+                }
+                opcode::DISCARD_CONTINUE => {
+                    self.frame_mut().continue_points_mut().pop();
+                }
+                opcode::RECORD_CONTINUE => {
+                    let offset = self.read_u32();
+                    let ip = unsafe { self.ip().add(offset as usize) };
+                    let local_len = self.local_stack.len();
+                    let handler_len = self.frame().handlers().len();
+                    self.frame_mut()
+                        .continue_points_mut()
+                        .push((ip, local_len, handler_len));
                 }
                 opcode::CALL => {
                     let arity = self.read_u32() as usize;
                     let callee = self.peek(arity).map(|x| x.clone()).unwrap();
-                    if !self.call_value(&callee, arity) {
-                        return InterpretStatus::RuntimeError;
+                    self.call_value(&callee, arity);
+                }
+                opcode::CALL_STACKED => {
+                    // the callee was pushed after its arguments, move it below them.
+                    let arity = self.read_u32() as usize;
+                    let callee = self.stack.pop().unwrap();
+                    let base = self.frame().slot_begin() + 1;
+                    if self.stack.len() < base + arity {
+                        self.runtime_error(
+                            format!("not enough values to take {} arguments.", arity).as_str(),
+                        );
+                        continue;
+                    }
+                    self.stack.insert(self.stack.len() - arity, callee);
+                    self.call_value(&callee, arity);
+                }
+                opcode::REVERSE => {
+                    let count = self.read_u32() as usize;
+                    let len = self.stack.len();
+                    self.stack[len - count..].reverse();
+                }
+                opcode::CLEAR => {
+                    let len = self.frame().slot_begin() + 1;
+                    self.stack.truncate(len);
+                }
+                opcode::SET_PREV => {
+                    let value = *self.peek(0).unwrap();
+                    self.frame_mut().set_prev(value);
+                }
+                opcode::GET_PREV => {
+                    let value = self.frame().prev();
+                    self.stack.push(value);
+                }
+                // the value is still on stack, under the operands of current statement.
+                opcode::TAKE_PREV => {
+                    let depth = self.read_u32() as usize;
+                    let value = self.stack.remove(self.stack.len() - 1 - depth);
+                    self.stack.push(value);
+                }
+                opcode::CHECK_TYPE => {
+                    let kind = Kind::from_u8(self.read_byte()).expect("unknown type.");
+                    let value = *self.peek(0).unwrap();
+                    if !kind.accepts(&value) {
+                        self.runtime_error(&format!(
+                            "expect a {} but got a {}.",
+                            kind.name(),
+                            Kind::name_of(&value)
+                        ));
+                        continue;
+                    }
+                }
+                opcode::LIST => {
+                    let id = self.runtime.add_list(vec![]);
+                    self.stack.push(Value::List(id));
+                }
+                opcode::APPEND => {
+                    let value = self.stack.pop().unwrap();
+                    match self.peek(0) {
+                        Some(Value::List(id)) => {
+                            let id = *id;
+                            self.runtime.get_list_mut(&id).push(value);
+                        }
+                        _ => {
+                            self.runtime_error("can only push values into a list.");
+                            continue;
+                        }
                     }
                 }
+                opcode::INDEX => {
+                    self.index();
+                }
+                opcode::OBJECT => {
+                    let id = self.runtime.add_object(Object::new());
+                    self.stack.push(Value::Object(id));
+                }
+                opcode::SET_INDEX => {
+                    self.set_index();
+                }
+                opcode::LENGTH => {
+                    let value = match self.stack.pop() {
+                        Some(Value::List(id)) => self.runtime.get_list(&id).len(),
+                        Some(Value::String(id)) => {
+                            self.runtime.interner().lookup(id).chars().count()
+                        }
+                        _ => {
+                            self.runtime_error("only list and string have length.");
+                            continue;
+                        }
+                    };
+                    self.stack.push(Value::Number(value as f64));
+                }
+                opcode::REST => {
+                    let value = match self.stack.pop() {
+                        Some(Value::List(id)) => {
+                            let rest = self.runtime.get_list(&id).iter().skip(1).cloned().collect();
+                            Value::List(self.runtime.add_list(rest))
+                        }
+                        Some(Value::String(id)) => {
+                            let rest = self
+                                .runtime
+                                .interner()
+                                .lookup(id)
+                                .chars()
+                                .skip(1)
+                                .collect::<String>();
+                            Value::String(self.runtime.interner_mut().intern(&rest))
+                        }
+                        _ => {
+                            self.runtime_error("only list and string have rest.");
+                            continue;
+                        }
+                    };
+                    self.stack.push(value);
+                }
+                opcode::TRY => {
+                    let offset = self.read_u32();
+                    let handler = Handler {
+                        ip: unsafe { self.ip().add(offset as usize) },
+                        stack_len: self.stack.len(),
+                        local_len: self.local_stack.len(),
+                        break_len: self.frame().break_points().len(),
+                        continue_len: self.frame().continue_points().len(),
+                    };
+                    self.frame_mut().handlers_mut().push(handler);
+                }
+                opcode::END_TRY => {
+                    self.frame_mut().handlers_mut().pop();
+                }
+                opcode::IMPORT => {
+                    let module = self.read_u32();
+                    // a book runs only once, later imports just read its globals.
+                    if let Entry::Vacant(entry) = self.globals.entry(module) {
+                        let module = self.runtime.get_module(module);
+                        if let Some(values) = module.values() {
+                            entry.insert(values.iter().cloned().collect());
+                            self.stack.push(Value::Nil);
+                        } else {
+                            entry.insert(HashMap::new());
+                            let closure = module.closure().unwrap();
+                            self.stack.push(Value::Closure(closure));
+                            self.call(closure, 0);
+                        }
+                    } else {
+                        self.stack.push(Value::Nil);
+                    }
+                }
+                opcode::IMPORT_NAME => {
+                    let module = self.read_u32();
+                    let str_id = self.read_str().expect("a valid str id");
+                    let str = self.runtime.interner().lookup(str_id);
+                    match self
+                        .globals
+                        .get(&module)
+                        .and_then(|globals| globals.get(str))
+                    {
+                        Some(value) => self.stack.push(*value),
+                        None => {
+                            self.runtime_error(format!("undefined variable {}", str).as_str());
+                            continue;
+                        }
+                    }
+                }
+                opcode::THROW => {
+                    self.error = self.stack.pop();
+                }
                 _ => {}
             }
         }
     }
+    // unwind to the nearest 姑妄行此 and hand it the error,
+    // returns false when nobody is there to catch it.
+    fn catch(&mut self, error: Value) -> bool {
+        let caught = self
+            .runtime
+            .frames()
+            .iter()
+            .any(|frame| !frame.handlers().is_empty());
+
+        if !caught {
+            eprintln!(
+                "[line {}] error: {}",
+                self.chunk().get_line(self.offset()),
+                self.format_value(&error)
+            );
+            self.stack.clear();
+            return false;
+        }
+
+        loop {
+            if let Some(handler) = self.frame_mut().handlers_mut().pop() {
+                self.stack.truncate(handler.stack_len);
+                self.truncate_locals(handler.local_len);
+                self.frame_mut()
+                    .break_points_mut()
+                    .truncate(handler.break_len);
+                self.frame_mut()
+                    .continue_points_mut()
+                    .truncate(handler.continue_len);
+                self.stack.push(error);
+                self.set_ip(handler.ip);
+                return true;
+            }
+
+            let local_len = self.frame().local_slot_begin();
+            self.runtime.exit_frame();
+            self.truncate_locals(local_len);
+        }
+    }
+    fn module(&self) -> ModuleId {
+        self.runtime
+            .closure_function(&self.frame().closure_id())
+            .module()
+    }
+    fn module_globals(&mut self) -> &mut HashMap<String, Value> {
+        let module = self.module();
+        self.globals.entry(module).or_default()
+    }
+    fn frame_up_value(&self, index: usize) -> UpValueId {
+        self.runtime
+            .get_closure(&self.frame().closure_id())
+            .up_values()[index]
+    }
+    // closures capturing the same variable share one up value.
+    fn capture_up_value(&mut self, slot: usize) -> UpValueId {
+        for id in self.open_up_values.iter() {
+            if let Captured::Open(s) = self.runtime.get_up_value(id) {
+                if *s == slot {
+                    return *id;
+                }
+            }
+        }
+
+        let id = self.runtime.add_up_value(Captured::Open(slot));
+        self.open_up_values.push(id);
+        id
+    }
+    // drop locals above `len`, moving the captured ones into their up values first.
+    fn truncate_locals(&mut self, len: usize) {
+        let runtime = &mut self.runtime;
+        let local_stack = &self.local_stack;
+        let deleted_locals = &self.deleted_locals;
+        self.open_up_values.retain(|id| {
+            let up_value = runtime.get_up_value_mut(id);
+            match *up_value {
+                Captured::Open(slot) if slot >= len => {
+                    *up_value = match deleted_locals.get(&slot) {
+                        Some(name) => Captured::Deleted(*name),
+                        None => {
+                            Captured::Closed(local_stack.get(slot).cloned().unwrap_or(Value::Nil))
+                        }
+                    };
+                    false
+                }
+                _ => true,
+            }
+        });
+
+        self.local_stack.truncate(len);
+        self.deleted_locals.retain(|slot, _| *slot < len);
+    }
+    // false if the local has been deleted, the error is raised already.
+    fn check_deleted(&mut self, slot: usize) -> bool {
+        match self.deleted_locals.get(&slot) {
+            Some(name) => {
+                let name = self.runtime.interner().lookup(*name).to_owned();
+                self.runtime_error(format!("undefined variable {}", name).as_str());
+                false
+            }
+            None => true,
+        }
+    }
     pub fn free(&mut self) {
         self.stack.clear();
         free_object(self.runtime)
     }
+    // errors raised by the vm are plain strings, so 豈「「...」」之禍歟 can catch them too.
     fn runtime_error(&mut self, msg: &str) {
-        eprintln!(
-            "[line {}] error: {}",
-            self.chunk().get_line(self.offset()),
-            msg
-        );
-
-        self.stack.clear();
+        let msg = self.runtime.interner_mut().intern(msg);
+        self.error = Some(Value::String(msg));
     }
     pub fn read_string(&mut self) -> Option<String> {
         let idx = self.read_constant().map(|x| x.clone());
@@ -402,22 +810,82 @@ impl<'a> VM<'a> {
     fn call_value(&mut self, callee: &Value, arity: usize) -> bool {
         match callee {
             Value::Closure(idx) => {
-                let fun = self.runtime.get_closure(idx).function();
+                let fun = self.runtime.closure_function(idx);
                 if arity != fun.arity() {
                     self.runtime_error(
-                        format!("expected {} arguments but got {}.", fun.arity(), arity).as_str(),
+                        format!(
+                            "{} expected {} arguments but got {}.",
+                            fun.name(),
+                            fun.arity(),
+                            arity
+                        )
+                        .as_str(),
                     );
                     return false;
                 }
 
+                // arguments are checked at the call, where the caller can see its own line.
+                if self.runtime.strict_types() {
+                    let args = &self.stack[self.stack.len() - arity..];
+                    let mismatch = fun
+                        .param_kinds()
+                        .iter()
+                        .zip(args)
+                        .find(|(kind, arg)| !kind.accepts(arg));
+                    if let Some((kind, arg)) = mismatch {
+                        let msg = format!(
+                            "{} expected a {} but got a {}.",
+                            fun.name(),
+                            kind.name(),
+                            Kind::name_of(arg)
+                        );
+                        self.runtime_error(&msg);
+                        return false;
+                    }
+                }
+
                 self.call(*idx, arity)
             }
+            Value::Native(id) => self.call_native(*id, arity),
             _ => {
                 self.runtime_error("can only call functions and classes.");
                 false
             }
         }
     }
+    // natives don't need a frame, the result takes the place of the callee right away.
+    fn call_native(&mut self, id: NativeId, arity: usize) -> bool {
+        let native = self.runtime.get_native(&id);
+        let function = native.function();
+        if arity != native.arity() {
+            let msg = format!(
+                "{} expected {} arguments but got {}.",
+                native.name(),
+                native.arity(),
+                arity
+            );
+            self.runtime_error(&msg);
+            return false;
+        }
+
+        let base = self.stack.len() - arity;
+        let args = self.stack[base..].to_vec();
+        match function(self, &args) {
+            Ok(value) => {
+                self.stack.truncate(base - 1);
+                self.stack.push(value);
+                true
+            }
+            Err(NativeError::Message(msg)) => {
+                self.runtime_error(&msg);
+                false
+            }
+            Err(NativeError::Throw(value)) => {
+                self.error = Some(value);
+                false
+            }
+        }
+    }
     fn call(&mut self, closure_idx: ClosureId, arity: usize) -> bool {
         self.runtime.begin_frame(
             closure_idx,
@@ -426,7 +894,127 @@ impl<'a> VM<'a> {
         );
         true
     }
+    // wenyan is one-based, 「甲」之一 is the first element.
+    fn list_position(&mut self, index: Value) -> Option<usize> {
+        match index {
+            Value::Number(num) if num >= 1.0 && num.fract() == 0.0 => Some(num as usize - 1),
+            _ => {
+                self.runtime_error("index should be a positive integer.");
+                None
+            }
+        }
+    }
+    fn index(&mut self) -> bool {
+        let index = self.stack.pop().unwrap();
+        let target = self.stack.pop().unwrap();
+
+        let value = match (target, index) {
+            (Value::Object(id), Value::String(key)) => {
+                match self.runtime.get_object(&id).get(key).cloned() {
+                    Some(value) => Some(value),
+                    None => {
+                        let key = self.runtime.interner().lookup(key).to_owned();
+                        self.runtime_error(format!("undefined property {}.", key).as_str());
+                        return false;
+                    }
+                }
+            }
+            (Value::List(id), index) => match self.list_position(index) {
+                Some(position) => self.runtime.get_list(&id).get(position).cloned(),
+                None => return false,
+            },
+            (Value::String(id), index) => match self.list_position(index) {
+                Some(position) => {
+                    let ch = self.runtime.interner().lookup(id).chars().nth(position);
+                    ch.map(|ch| Value::String(self.runtime.interner_mut().intern(&ch.to_string())))
+                }
+                None => return false,
+            },
+            _ => {
+                self.runtime_error("can only index a list, string or object.");
+                return false;
+            }
+        };
+
+        match value {
+            Some(value) => {
+                self.stack.push(value);
+                true
+            }
+            None => {
+                self.runtime_error("index out of range.");
+                false
+            }
+        }
+    }
+    fn set_index(&mut self) -> bool {
+        let value = self.stack.pop().unwrap();
+        let index = self.stack.pop().unwrap();
+        let target = *self.peek(0).unwrap();
+
+        match (target, index) {
+            (Value::Object(id), Value::String(key)) => {
+                self.runtime.get_object_mut(&id).set(key, value);
+                true
+            }
+            (Value::List(id), index) => {
+                let position = match self.list_position(index) {
+                    Some(position) => position,
+                    None => return false,
+                };
+                match self.runtime.get_list_mut(&id).get_mut(position) {
+                    Some(item) => {
+                        *item = value;
+                        true
+                    }
+                    None => {
+                        self.runtime_error("index out of range.");
+                        false
+                    }
+                }
+            }
+            _ => {
+                self.runtime_error("can only set element of a list or object.");
+                false
+            }
+        }
+    }
+    fn delete_index(&mut self) -> bool {
+        let index = self.stack.pop().unwrap();
+        let target = self.stack.pop().unwrap();
+
+        match (target, index) {
+            (Value::Object(id), Value::String(key)) => {
+                self.runtime.get_object_mut(&id).remove(key);
+                true
+            }
+            // the elements after it move forward, like 「甲」之其餘.
+            (Value::List(id), index) => {
+                let position = match self.list_position(index) {
+                    Some(position) => position,
+                    None => return false,
+                };
+                let list = self.runtime.get_list_mut(&id);
+                if position < list.len() {
+                    list.remove(position);
+                    true
+                } else {
+                    self.runtime_error("index out of range.");
+                    false
+                }
+            }
+            _ => {
+                self.runtime_error("can only delete element of a list or object.");
+                false
+            }
+        }
+    }
     fn format_value(&self, value: &Value) -> String {
+        self.format_nested(value, &mut vec![])
+    }
+    // `outer` holds the lists and objects being formatted around `value`,
+    // meeting one of them again means it contains itself.
+    fn format_nested(&self, value: &Value, outer: &mut Vec<Value>) -> String {
         match value {
             Value::Nil => {
                 format!("undefined")
@@ -441,15 +1029,56 @@ impl<'a> VM<'a> {
                 format!("{}", self.runtime.interner().lookup(*str))
             }
             Value::Closure(idx) => {
-                let name = self.runtime.get_closure(idx).function().name();
+                let function = self.runtime.closure_function(idx);
 
-                format!(
-                    "<fn> {}",
-                    if name == "" { "<global context>" } else { name }
-                )
+                if function.name() == "" {
+                    String::from("<fn> <global context>")
+                } else {
+                    format!("<fn> {}", function.signature())
+                }
+            }
+            Value::List(idx) => {
+                if outer.iter().any(|x| value_equal(*x, *value)) {
+                    return String::from("[…]");
+                }
+                outer.push(*value);
+                let items = self
+                    .runtime
+                    .get_list(idx)
+                    .iter()
+                    .map(|x| self.format_nested(x, outer))
+                    .collect::<Vec<String>>();
+                outer.pop();
+
+                format!("[{}]", items.join(", "))
+            }
+            Value::Object(idx) => {
+                if outer.iter().any(|x| value_equal(*x, *value)) {
+                    return String::from("{…}");
+                }
+                outer.push(*value);
+                let properties = self
+                    .runtime
+                    .get_object(idx)
+                    .properties()
+                    .iter()
+                    .map(|(key, value)| {
+                        format!(
+                            "{}: {}",
+                            self.runtime.interner().lookup(*key),
+                            self.format_nested(value, outer)
+                        )
+                    })
+                    .collect::<Vec<String>>();
+                outer.pop();
+
+                format!("{{{}}}", properties.join(", "))
+            }
+            Value::Function(idx) => {
+                format!("<fn> {}", self.runtime.get_function(idx).signature())
             }
-            Value::Function(_) => {
-                panic!("unreachable")
+            Value::Native(id) => {
+                format!("<native fn> {}", self.runtime.get_native(id).name())
             }
         }
     }
@@ -457,30 +1086,40 @@ impl<'a> VM<'a> {
         let id = self.read_u32() as usize;
         self.chunk().constants().get(id)
     }
-    fn binary_op(&mut self, op: &str) {
+    fn binary_op(&mut self, op: u8) -> bool {
         let slice_start = self.stack.len() - 2;
         let op_code = self.read_byte();
 
-        let compute = |a, b| match op {
-            "+" => a + b,
-            "-" => a - b,
-            "*" => a * b,
-            _ => panic!("unreachable"),
-        };
-
         match &self.stack[slice_start..] {
             [Value::Number(a), Value::Number(b)] => {
-                let num = match op_code {
-                    opcode::PREPOSITION_LEFT => compute(*b, *a),
-                    opcode::PREPOSITION_RIGHT => compute(*a, *b),
+                let (left, right) = match op_code {
+                    opcode::PREPOSITION_LEFT => (*b, *a),
+                    opcode::PREPOSITION_RIGHT => (*a, *b),
+                    _ => panic!("unreachable"),
+                };
+
+                // wenyan numbers are JavaScript numbers, but silently producing
+                // Infinity or NaN hides the bug, so we report it instead.
+                if (op == opcode::DIVIDE || op == opcode::REMAINDER) && right == 0.0 {
+                    self.runtime_error("division by zero.");
+                    return false;
+                }
+
+                let num = match op {
+                    opcode::ADD => left + right,
+                    opcode::SUBTRACT => left - right,
+                    opcode::MULTIPLY => left * right,
+                    opcode::DIVIDE => left / right,
+                    opcode::REMAINDER => left % right,
                     _ => panic!("unreachable"),
                 };
                 self.stack.pop();
                 self.stack.pop();
                 self.stack.push(Value::Number(num));
+                true
             }
             [Value::String(a), Value::String(b)] => {
-                if op == "+" {
+                if op == opcode::ADD {
                     let str = format!(
                         "{}{}",
                         self.runtime.interner().lookup(*a),
@@ -490,12 +1129,15 @@ impl<'a> VM<'a> {
                     self.stack.pop();
                     let str_id = self.runtime.interner_mut().intern(&str);
                     self.stack.push(Value::String(str_id));
+                    true
                 } else {
                     self.runtime_error("two string can only be added");
+                    false
                 }
             }
             _ => {
                 self.runtime_error("Operands must be numbers.");
+                false
             }
         }
     }
@@ -528,6 +1170,12 @@ impl<'a> VM<'a> {
             opcode::MULTIPLY => {
                 self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_MULTIPLY")
             }
+            opcode::DIVIDE => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_DIVIDE")
+            }
+            opcode::REMAINDER => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_REMAINDER")
+            }
             opcode::NIL => {
                 self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_NIL")
             }
@@ -581,6 +1229,21 @@ impl<'a> VM<'a> {
             opcode::POP => {
                 self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_POP")
             }
+            opcode::DELETE_GLOBAL => {
+                self.constant_instruction(&mut opcode_metadata, offset, "OP_DELETE_GLOBAL")
+            }
+            opcode::DELETE_LOCAL => {
+                print!(" {:<20}", "OP_DELETE_LOCAL");
+                let slot = self.chunk().get_u32(offset + 1);
+                let constant = self.chunk().get_u32(offset + 5);
+                print!(" {:08}", slot);
+                let value = self.chunk().constants().get(constant as usize).unwrap();
+                print!(" {}", self.format_value(value));
+                offset + 9
+            }
+            opcode::DELETE_INDEX => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_DELETE_INDEX")
+            }
             opcode::POP_LOCAL => {
                 self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_POP_LOCAL")
             }
@@ -596,14 +1259,97 @@ impl<'a> VM<'a> {
                 self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_BREAK")
             }
             opcode::RECORD_BREAK => self.jump_instruction(1, offset, "OP_RECORD_BREAK"),
+            opcode::CONTINUE => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_CONTINUE")
+            }
+            opcode::DISCARD_CONTINUE => self.disassemble_simple_instruction(
+                &mut opcode_metadata,
+                offset,
+                "OP_DISCARD_CONTINUE",
+            ),
+            opcode::RECORD_CONTINUE => self.jump_instruction(1, offset, "OP_RECORD_CONTINUE"),
+            opcode::TRY => self.jump_instruction(1, offset, "OP_TRY"),
+            opcode::IMPORT => self.byte_instruction(&mut opcode_metadata, offset, "OP_IMPORT"),
+            opcode::IMPORT_NAME => {
+                print!(" {:<20}", "OP_IMPORT_NAME");
+                let module = self.chunk().get_u32(offset + 1);
+                let constant = self.chunk().get_u32(offset + 5);
+                print!(" {:08}", module);
+                print!(" {:08}", constant);
+                let value = self.chunk().constants().get(constant as usize).unwrap();
+                print!(" {}", self.format_value(value));
+                offset + 9
+            }
+            opcode::GET_UPVALUE => {
+                self.byte_instruction(&mut opcode_metadata, offset, "OP_GET_UPVALUE")
+            }
+            opcode::SET_UPVALUE => {
+                self.byte_instruction(&mut opcode_metadata, offset, "OP_SET_UPVALUE")
+            }
+            opcode::END_TRY => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_END_TRY")
+            }
+            opcode::THROW => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_THROW")
+            }
             opcode::CALL => self.byte_instruction(&mut opcode_metadata, offset, "OP_CALL"),
+            opcode::CALL_STACKED => {
+                self.byte_instruction(&mut opcode_metadata, offset, "OP_CALL_STACKED")
+            }
+            opcode::REVERSE => self.byte_instruction(&mut opcode_metadata, offset, "OP_REVERSE"),
+            opcode::CLEAR => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_CLEAR")
+            }
+            opcode::SET_PREV => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_SET_PREV")
+            }
+            opcode::GET_PREV => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_GET_PREV")
+            }
+            opcode::TAKE_PREV => {
+                self.byte_instruction(&mut opcode_metadata, offset, "OP_TAKE_PREV")
+            }
+            opcode::CHECK_TYPE => {
+                print!(" {:<20}", "OP_CHECK_TYPE");
+                let kind = self.chunk().code()[offset + 1];
+                print!(
+                    " {}",
+                    Kind::from_u8(kind).map(|kind| kind.name()).unwrap_or("?")
+                );
+                offset + 2
+            }
+            opcode::LIST => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_LIST")
+            }
+            opcode::APPEND => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_APPEND")
+            }
+            opcode::INDEX => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_INDEX")
+            }
+            opcode::OBJECT => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_OBJECT")
+            }
+            opcode::SET_INDEX => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_SET_INDEX")
+            }
+            opcode::LENGTH => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_LENGTH")
+            }
+            opcode::REST => {
+                self.disassemble_simple_instruction(&mut opcode_metadata, offset, "OP_REST")
+            }
             opcode::CLOSURE => {
                 print!(" {:<20}", "OP_CLOSURE");
                 let constant = self.chunk().get_u32(offset + 1);
                 print!(" {:08}", constant);
                 let value = self.chunk().constants().get(constant as usize).unwrap();
                 print!(" {}", self.format_value(value));
-                offset + 5
+                let count = match value {
+                    Value::Function(idx) => self.runtime.get_function(idx).up_value_count(),
+                    _ => 0,
+                };
+                offset + 5 + count * 5
             }
             _ => {
                 // this is a unknown opcode
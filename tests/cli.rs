@@ -14,6 +14,28 @@ fn run(input_file: &str, expected_file: &str) -> TestResult {
     Ok(())
 }
 
+fn run_with_error(input_file: &str, expected_file: &str, expected_error_file: &str) -> TestResult {
+    let expected = fs::read_to_string(expected_file)?;
+    let expected_error = fs::read_to_string(expected_error_file)?;
+    Command::cargo_bin("wyw")?
+        .arg(input_file)
+        .assert()
+        .stdout(expected)
+        .stderr(expected_error);
+    Ok(())
+}
+
+fn run_with_args(args: &[&str], expected_file: &str, expected_error_file: &str) -> TestResult {
+    let expected = fs::read_to_string(expected_file)?;
+    let expected_error = fs::read_to_string(expected_error_file)?;
+    Command::cargo_bin("wyw")?
+        .args(args)
+        .assert()
+        .stdout(expected)
+        .stderr(expected_error);
+    Ok(())
+}
+
 #[test]
 fn test_basic_type() -> TestResult {
     run(
@@ -30,6 +52,23 @@ fn test_binary_statement() -> TestResult {
     )
 }
 
+#[test]
+fn test_divide_statement() -> TestResult {
+    run(
+        "tests/inputs/divide-statement.wy",
+        "tests/expected/divide-statement.out.txt",
+    )
+}
+
+#[test]
+fn test_divide_by_zero() -> TestResult {
+    run_with_error(
+        "tests/inputs/divide-by-zero.wy",
+        "tests/expected/divide-by-zero.out.txt",
+        "tests/expected/divide-by-zero.err.txt",
+    )
+}
+
 #[test]
 fn test_unary_statement() -> TestResult {
     run(
@@ -78,6 +117,22 @@ fn test_if_statement() -> TestResult {
     )
 }
 
+#[test]
+fn test_else_if_statement() -> TestResult {
+    run(
+        "tests/inputs/else-if-statement.wy",
+        "tests/expected/else-if-statement.out.txt",
+    )
+}
+
+#[test]
+fn test_if_prev_statement() -> TestResult {
+    run(
+        "tests/inputs/if-prev-statement.wy",
+        "tests/expected/if-prev-statement.out.txt",
+    )
+}
+
 #[test]
 fn test_bool_algebra_statement() -> TestResult {
     run(
@@ -102,6 +157,31 @@ fn test_for_enum_statement() -> TestResult {
     )
 }
 
+#[test]
+fn test_continue_statement() -> TestResult {
+    run(
+        "tests/inputs/continue-statement.wy",
+        "tests/expected/continue-statement.out.txt",
+    )
+}
+
+#[test]
+fn test_break_in_function() -> TestResult {
+    run(
+        "tests/inputs/break-in-function.wy",
+        "tests/expected/break-in-function.out.txt",
+    )
+}
+
+#[test]
+fn test_break_outside_loop() -> TestResult {
+    run_with_error(
+        "tests/inputs/break-outside-loop.wy",
+        "tests/expected/break-outside-loop.out.txt",
+        "tests/expected/break-outside-loop.err.txt",
+    )
+}
+
 #[test]
 fn test_fun_statement() -> TestResult {
     run(
@@ -125,3 +205,290 @@ fn test_multiplication_table() -> TestResult {
         "tests/expected/multiplication-table.out.txt",
     )
 }
+
+#[test]
+fn test_list_statement() -> TestResult {
+    run(
+        "tests/inputs/list-statement.wy",
+        "tests/expected/list-statement.out.txt",
+    )
+}
+
+#[test]
+fn test_object_statement() -> TestResult {
+    run(
+        "tests/inputs/object-statement.wy",
+        "tests/expected/object-statement.out.txt",
+    )
+}
+
+#[test]
+fn test_object_name_error() -> TestResult {
+    run_with_error(
+        "tests/inputs/object-name-error.wy",
+        "tests/expected/object-name-error.out.txt",
+        "tests/expected/object-name-error.err.txt",
+    )
+}
+
+#[test]
+fn test_cycle_print() -> TestResult {
+    run(
+        "tests/inputs/cycle-print.wy",
+        "tests/expected/cycle-print.out.txt",
+    )
+}
+
+#[test]
+fn test_for_each_statement() -> TestResult {
+    run(
+        "tests/inputs/for-each-statement.wy",
+        "tests/expected/for-each-statement.out.txt",
+    )
+}
+
+#[test]
+fn test_try_statement() -> TestResult {
+    run(
+        "tests/inputs/try-statement.wy",
+        "tests/expected/try-statement.out.txt",
+    )
+}
+
+#[test]
+fn test_throw_uncaught() -> TestResult {
+    run_with_error(
+        "tests/inputs/throw-uncaught.wy",
+        "tests/expected/throw-uncaught.out.txt",
+        "tests/expected/throw-uncaught.err.txt",
+    )
+}
+
+#[test]
+fn test_closure_statement() -> TestResult {
+    run(
+        "tests/inputs/closure-statement.wy",
+        "tests/expected/closure-statement.out.txt",
+    )
+}
+
+#[test]
+fn test_local_fun_statement() -> TestResult {
+    run_with_error(
+        "tests/inputs/local-fun-statement.wy",
+        "tests/expected/local-fun-statement.out.txt",
+        "tests/expected/local-fun-statement.err.txt",
+    )
+}
+
+#[test]
+fn test_import_statement() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/import-statement.out.txt")?;
+    Command::cargo_bin("wyw")?
+        .args([
+            "-I",
+            "tests/inputs/books",
+            "tests/inputs/import-statement.wy",
+        ])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+#[test]
+fn test_import_private() -> TestResult {
+    run_with_error(
+        "tests/inputs/import-private.wy",
+        "tests/expected/import-private.out.txt",
+        "tests/expected/import-private.err.txt",
+    )
+}
+
+#[test]
+fn test_import_circular() -> TestResult {
+    run_with_error(
+        "tests/inputs/import-circular.wy",
+        "tests/expected/import-circular.out.txt",
+        "tests/expected/import-circular.err.txt",
+    )
+}
+
+#[test]
+fn test_macro_statement() -> TestResult {
+    run_with_error(
+        "tests/inputs/macro-statement.wy",
+        "tests/expected/macro-statement.out.txt",
+        "tests/expected/macro-statement.err.txt",
+    )
+}
+
+#[test]
+fn test_comment_statement() -> TestResult {
+    run_with_error(
+        "tests/inputs/comment-statement.wy",
+        "tests/expected/comment-statement.out.txt",
+        "tests/expected/comment-statement.err.txt",
+    )
+}
+
+#[test]
+fn test_call_statement() -> TestResult {
+    run_with_error(
+        "tests/inputs/call-statement.wy",
+        "tests/expected/call-statement.out.txt",
+        "tests/expected/call-statement.err.txt",
+    )
+}
+
+#[test]
+fn test_prev_statement() -> TestResult {
+    run(
+        "tests/inputs/prev-statement.wy",
+        "tests/expected/prev-statement.out.txt",
+    )
+}
+
+#[test]
+fn test_strict_types() -> TestResult {
+    run_with_args(
+        &["--strict-types", "tests/inputs/strict-types.wy"],
+        "tests/expected/strict-types.out.txt",
+        "tests/expected/strict-types.err.txt",
+    )
+}
+
+#[test]
+fn test_strict_types_runtime() -> TestResult {
+    run_with_args(
+        &["--strict-types", "tests/inputs/strict-types-runtime.wy"],
+        "tests/expected/strict-types-runtime.out.txt",
+        "tests/expected/strict-types-runtime.err.txt",
+    )
+}
+
+#[test]
+fn test_fun_signature() -> TestResult {
+    run(
+        "tests/inputs/fun-signature.wy",
+        "tests/expected/fun-signature.out.txt",
+    )
+}
+
+#[test]
+fn test_fun_signature_error() -> TestResult {
+    run_with_error(
+        "tests/inputs/fun-signature-error.wy",
+        "tests/expected/fun-signature-error.out.txt",
+        "tests/expected/fun-signature-error.err.txt",
+    )
+}
+
+#[test]
+fn test_fun_too_many_params() -> TestResult {
+    run_with_error(
+        "tests/inputs/fun-too-many-params.wy",
+        "tests/expected/fun-too-many-params.out.txt",
+        "tests/expected/fun-too-many-params.err.txt",
+    )
+}
+
+#[test]
+fn test_return_statement() -> TestResult {
+    run(
+        "tests/inputs/return-statement.wy",
+        "tests/expected/return-statement.out.txt",
+    )
+}
+
+#[test]
+fn test_return_top_level() -> TestResult {
+    run_with_error(
+        "tests/inputs/return-top-level.wy",
+        "tests/expected/return-top-level.out.txt",
+        "tests/expected/return-top-level.err.txt",
+    )
+}
+
+#[test]
+fn test_delete_statement() -> TestResult {
+    run_with_error(
+        "tests/inputs/delete-statement.wy",
+        "tests/expected/delete-statement.out.txt",
+        "tests/expected/delete-statement.err.txt",
+    )
+}
+
+#[test]
+fn test_delete_captured() -> TestResult {
+    run_with_error(
+        "tests/inputs/delete-captured.wy",
+        "tests/expected/delete-captured.out.txt",
+        "tests/expected/delete-captured.err.txt",
+    )
+}
+
+#[test]
+fn test_delete_closed() -> TestResult {
+    run_with_error(
+        "tests/inputs/delete-closed.wy",
+        "tests/expected/delete-closed.out.txt",
+        "tests/expected/delete-closed.err.txt",
+    )
+}
+
+#[test]
+fn test_math_book() -> TestResult {
+    run_with_error(
+        "tests/inputs/math-book.wy",
+        "tests/expected/math-book.out.txt",
+        "tests/expected/math-book.err.txt",
+    )
+}
+
+// every export of 算經 once, the expected numbers are what node v20's Math prints
+// for the same calls, not output of the original wenyan 算經.
+#[test]
+fn test_math_reference() -> TestResult {
+    run(
+        "tests/inputs/math-reference.wy",
+        "tests/expected/math-reference.out.txt",
+    )
+}
+
+#[test]
+fn test_bits_book() -> TestResult {
+    run_with_error(
+        "tests/inputs/bits-book.wy",
+        "tests/expected/bits-book.out.txt",
+        "tests/expected/bits-book.err.txt",
+    )
+}
+
+#[test]
+fn test_random_book() -> TestResult {
+    run_with_args(
+        &["--seed", "42", "tests/inputs/random-book.wy"],
+        "tests/expected/random-book.out.txt",
+        "tests/expected/random-book.err.txt",
+    )
+}
+
+// only the parts of 曆法 that don't read the clock.
+#[test]
+fn test_calendar_book() -> TestResult {
+    run_with_error(
+        "tests/inputs/calendar-book.wy",
+        "tests/expected/calendar-book.out.txt",
+        "tests/expected/calendar-book.err.txt",
+    )
+}
+
+#[test]
+fn test_import_failed() -> TestResult {
+    run_with_args(
+        &["-I", "tests/inputs/books", "tests/inputs/import-failed.wy"],
+        "tests/expected/import-failed.out.txt",
+        "tests/expected/import-failed.err.txt",
+    )
+}